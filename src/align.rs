@@ -1,8 +1,22 @@
+//! Sequence alignment entrypoint.
+
 use anyhow::bail;
 
 use crate::{
-    ceil_div, cigar::EditOp, config::AlignConfig, equal::EqualityDefinition, mode::AlignMode,
-    peq::build_peq_table, task::AlignTask,
+    block::Block,
+    bounds,
+    budget::{raise_lower_bound, Meter},
+    ceil_div,
+    cigar::EditOp,
+    config::{AlignConfig, TracebackOrder},
+    dst::find_start_location,
+    equal::{EqualityDefinition, EqualityPair},
+    mode::AlignMode,
+    peq::{build_peq_table, QueryProfile},
+    task::AlignTask,
+    trace::Trace,
+    track::ScoreTrack,
+    unique::Uniqueness,
 };
 
 /// Alias for single u64 bitvec word.
@@ -47,6 +61,13 @@ pub struct Alignment {
     pub alignment: Option<Vec<EditOp>>,
     /// Number of different characters in query and target together.
     pub alphabet_length: usize,
+    /// Best and second-best distinct hit. Only if [`AlignConfig::uniqueness`] is set.
+    pub uniqueness: Option<Uniqueness>,
+    /// Record of calculated columns. Only if [`AlignConfig::trace`] is set.
+    pub trace: Option<Trace>,
+    /// Largest `k` tried, if edit distance is larger than it.
+    /// * Set if no alignment was found within `k`, `k_fraction` or the maximum of [`KGrowth`](crate::config::KGrowth).
+    pub exhausted_k: Option<usize>,
 }
 
 /// Transform sequences to sequences of indices.
@@ -74,7 +95,7 @@ pub fn transform_sequences(query: &str, target: &str) -> (String, Vec<usize>, Ve
                 unsafe {
                     // Set char to be in alphabet and assign letter idx.
                     *in_alphabet.get_unchecked_mut(elem_idx) = true;
-                    *letter_idx.get_unchecked_mut(elem_idx) = Some(alphabet.len())
+                    *letter_idx.get_unchecked_mut(elem_idx) = Some(alphabet.chars().count())
                 }
                 alphabet.push(elem)
             };
@@ -90,12 +111,65 @@ pub fn transform_sequences(query: &str, target: &str) -> (String, Vec<usize>, Ve
     (alphabet, transformed_query, transformed_target)
 }
 
+/// Transform query to sequence of indices for a target that is not known in advance.
+/// * Alphabet is fixed by query and added equalities.
+/// * All other characters are transformed to an extra character not equal to any query character.
+///
+/// # Returns
+/// * Alphabet, transformed index of each character, and transformed query.
+///   Error if query or added equalities have characters beyond [`MAX_UCHAR`].
+pub(crate) fn transform_query(
+    query: &str,
+    added_equalities: &[EqualityPair],
+) -> anyhow::Result<(String, [usize; MAX_UCHAR], Vec<usize>)> {
+    let equality_chrs = added_equalities
+        .iter()
+        .flat_map(|equality| [equality.first, equality.second]);
+    if let Some(chr) = query
+        .chars()
+        .chain(equality_chrs)
+        .find(|chr| *chr as usize >= MAX_UCHAR)
+    {
+        bail!("Character {chr} is beyond the {MAX_UCHAR} supported characters.")
+    }
+    let (mut alphabet, transformed_query, _) = transform_sequences(query, "");
+    // Target characters only equal to query characters through added equalities.
+    for equality in added_equalities.iter() {
+        for chr in [equality.first, equality.second] {
+            if !alphabet.contains(chr) {
+                alphabet.push(chr)
+            }
+        }
+    }
+    let unknown_idx = alphabet.chars().count();
+    let Some(unknown) = (0..=127)
+        .map(char::from)
+        .find(|chr| !alphabet.contains(*chr))
+    else {
+        bail!("No ASCII character left outside of alphabet {alphabet}.")
+    };
+    alphabet.push(unknown);
+
+    let mut letter_idx = [unknown_idx; MAX_UCHAR];
+    for (idx, chr) in alphabet.chars().enumerate() {
+        if let Some(letter) = letter_idx.get_mut(chr as usize) {
+            *letter = idx
+        }
+    }
+    Ok((alphabet, letter_idx, transformed_query))
+}
+
 /// Alignment information.
 pub struct AlignmentData {
+    /// Bit vector Pv of each block for each column.
     pub ps: Vec<Option<Word>>,
+    /// Bit vector Mv of each block for each column.
     pub ms: Vec<Option<Word>>,
+    /// Score of each block for each column.
     pub scores: Vec<Option<isize>>,
+    /// First block of band for each column.
     pub first_blocks: Vec<Option<usize>>,
+    /// Last block of band for each column.
     pub last_blocks: Vec<Option<usize>>,
 }
 
@@ -114,6 +188,30 @@ impl AlignmentData {
             last_blocks: vec![None; target_len],
         }
     }
+
+    /// Score of a cell, if within band of its column.
+    ///
+    /// # Arguments
+    /// * `max_num_blocks`: Number of blocks needed to cover the whole query.
+    /// * `row`: Zero-based position in query.
+    /// * `column`: Zero-based position in target.
+    pub fn cell(&self, max_num_blocks: usize, row: usize, column: usize) -> Option<isize> {
+        let word_size = WORD_SIZE as usize;
+        let block_idx = row / word_size;
+        let (first_block_idx, last_block_idx) =
+            (self.first_blocks[column]?, self.last_blocks[column]?);
+        if block_idx < first_block_idx || block_idx > last_block_idx {
+            return None;
+        }
+        let idx = column * max_num_blocks + block_idx;
+        let block = Block {
+            p: self.ps[idx]?,
+            m: self.ms[idx]?,
+            score: self.scores[idx]?,
+        };
+        // Offset from bottom cell of block.
+        Some(block.get_cell_value(word_size - 1 - row % word_size))
+    }
 }
 
 impl Alignment {
@@ -145,26 +243,41 @@ impl Alignment {
         let (query, target) = (query.as_ref(), target.as_ref());
 
         let (alphabet, transformed_query, transformed_target) = transform_sequences(query, target);
-        alignment.alphabet_length = alphabet.len();
+        alignment.alphabet_length = alphabet.chars().count();
+
+        let threshold = config.threshold(transformed_query.len())?;
+        if config.uniqueness {
+            if config.mode == AlignMode::NW {
+                bail!("Uniqueness requires AlignMode::HW or AlignMode::SHW.")
+            }
+            // Without k, second-best hit would always be found within default k.
+            if threshold.is_none() {
+                bail!("Uniqueness requires k.")
+            }
+        }
 
         // Special case where one of seq is empty.
         if transformed_query.is_empty() || transformed_target.is_empty() {
-            match config.mode {
-                // Global alignment.
-                AlignMode::NW => {
-                    // Completely different.
-                    alignment.edit_distance = Some(std::cmp::max(
-                        transformed_query.len(),
-                        transformed_target.len(),
-                    ));
-                    // Couldn't this potentially overflow?
-                    // https://github.com/Martinsos/edlib/blob/931be2b0909985551eb17d767694a6e64e31ebfa/edlib/src/edlib.cpp#L165
-                    alignment.end_locations = Some(vec![transformed_target.len() as isize - 1])
-                }
-                AlignMode::SHW | AlignMode::HW => {
-                    alignment.edit_distance = Some(transformed_query.len());
-                    alignment.end_locations = Some(vec![-1])
-                }
+            // Only global alignment has to align target. Otherwise, gap after query is free.
+            let aln_target: &[usize] = match config.mode {
+                AlignMode::NW => &transformed_target,
+                AlignMode::SHW | AlignMode::HW => &[],
+            };
+            // Completely different.
+            let edit_distance = std::cmp::max(transformed_query.len(), aln_target.len());
+            // Growth of k would stop at its maximum.
+            let max_k = threshold.or(config.k_growth.max);
+            if max_k.is_some_and(|k| edit_distance > k) {
+                alignment.exhausted_k = max_k;
+                return Ok(alignment);
+            }
+            alignment.edit_distance = Some(edit_distance);
+            alignment.end_locations = Some(vec![isize::try_from(aln_target.len())? - 1]);
+            if matches!(config.task, AlignTask::Loc | AlignTask::Path) {
+                alignment.start_locations = Some(vec![0]);
+            }
+            if config.task == AlignTask::Path {
+                alignment.alignment = gap_path(&transformed_query, aln_target);
             }
             return Ok(alignment);
         }
@@ -179,22 +292,66 @@ impl Alignment {
         // Number of redundant cells in last level blocks.
         let w = max_num_blocks * word_size - transformed_query.len();
         let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
-        let peq = build_peq_table(alphabet.len(), &transformed_query, &equality_def)?;
+        let profile =
+            QueryProfile::new(alignment.alphabet_length, &transformed_query, &equality_def)?;
+        let peq = &profile.peq;
 
         // Main Calculation
         let mut position_nw = None;
-        // let mut align_data = AlignmentData::new(max_num_blocks, target.len());
-        let mut dynamic_k = false;
-        let mut k = config.k.unwrap_or_else(|| {
-            dynamic_k = true;
-            word_size
-        });
-
-        loop {
-            match config.mode {
-                AlignMode::NW => {
-                    alignment.calc_edit_dst_nw(
-                        &peq,
+        let mut meter = Meter::new(config.budget.clone(), config.progress.clone());
+        if config.trace {
+            meter.trace = Some(Trace::new(transformed_query.len()));
+        }
+
+        if config.uniqueness {
+            // Single pass without lowering k.
+            let Some(k) = threshold else {
+                bail!("Uniqueness requires k.")
+            };
+            alignment.calc_uniqueness(
+                ScoreTrack::new(profile.clone(), transformed_target.clone(), k, &config.mode)?,
+                &mut meter,
+            )?;
+            if alignment.edit_distance.is_none() {
+                alignment.exhausted_k = Some(k);
+            }
+        } else {
+            let growth = &config.k_growth;
+            if threshold.is_none() && (growth.start == 0 || growth.factor < 2) {
+                bail!("Invalid k growth {growth:?}.")
+            }
+            // Symbol counts are only comparable without added equalities.
+            let mut lower = if config.added_equalities.is_empty() {
+                bounds::lower_bound(&transformed_query, &transformed_target, &config.mode)
+            } else {
+                bounds::length_lower_bound(
+                    transformed_query.len(),
+                    transformed_target.len(),
+                    &config.mode,
+                )
+            };
+            // Alignment is certainly found with k of upper bound.
+            let upper = bounds::upper_bound(&transformed_query, &transformed_target, &config.mode);
+
+            let mut k = match threshold {
+                Some(threshold) => std::cmp::min(threshold, upper),
+                None => {
+                    let start = std::cmp::max(std::cmp::min(growth.start, upper), lower);
+                    growth
+                        .max
+                        .map_or(start, |max_k| std::cmp::min(start, max_k))
+                }
+            };
+            // Skip calculation if no alignment can be within k.
+            let max_k = threshold.or(growth.max);
+            if max_k.is_some_and(|max_k| lower > max_k) {
+                alignment.exhausted_k = max_k;
+            }
+
+            while alignment.exhausted_k.is_none() {
+                match config.mode {
+                    AlignMode::NW => alignment.calc_edit_dst_nw(
+                        peq,
                         w,
                         max_num_blocks,
                         transformed_query.len(),
@@ -203,154 +360,258 @@ impl Alignment {
                         &mut position_nw,
                         None,
                         None,
-                    )?;
+                        &mut meter,
+                    ),
+                    AlignMode::SHW | AlignMode::HW => alignment.calc_edit_dst_semi_global(
+                        peq,
+                        w,
+                        max_num_blocks,
+                        transformed_query.len(),
+                        &transformed_target,
+                        k,
+                        &config.mode,
+                        &mut meter,
+                    ),
                 }
-                AlignMode::SHW | AlignMode::HW => alignment.calc_edit_dst_semi_global(
-                    &peq,
-                    w,
-                    max_num_blocks,
-                    transformed_query.len(),
-                    &transformed_target,
-                    k,
-                    &config.mode,
-                )?,
-            };
-
-            k *= 2;
+                .map_err(|err| raise_lower_bound(err, lower))?;
 
-            if !(dynamic_k && alignment.edit_distance.is_none()) {
-                break;
+                if alignment.edit_distance.is_some() {
+                    break;
+                }
+                lower = std::cmp::max(lower, k + 1);
+                if threshold.is_some() || growth.max.is_some_and(|max_k| k >= max_k) {
+                    alignment.exhausted_k = Some(k);
+                }
+                k = std::cmp::min(k.saturating_mul(growth.factor), upper);
+                if let Some(max_k) = growth.max {
+                    k = std::cmp::min(k, max_k);
+                }
             }
         }
 
         // If there is a solution.
-        if alignment.edit_distance.is_some() {
+        if let Some(edit_distance) = alignment.edit_distance {
             // If NW mode, set end location explicitly.
             if config.mode == AlignMode::NW {
                 alignment.end_locations = Some(vec![(transformed_target.len() - 1).try_into()?])
             }
 
             if matches!(config.task, AlignTask::Loc | AlignTask::Path) {
+                let end_locations = alignment.end_locations.as_ref().expect("No end locations.");
+                // If mode is SHW or NW, alignment always starts at beginning of target.
+                let mut start_locations = vec![0; end_locations.len()];
+
                 if config.mode == AlignMode::HW {
-                    let rev_transformed_target: Vec<usize> =
-                        transformed_target.iter().rev().copied().collect();
                     let rev_transformed_query: Vec<usize> =
                         transformed_query.iter().rev().copied().collect();
+                    let rev_peq = build_peq_table(
+                        alignment.alphabet_length,
+                        &rev_transformed_query,
+                        &equality_def,
+                    )?;
 
-                    let rev_peq =
-                        build_peq_table(alphabet.len(), &rev_transformed_query, &equality_def)?;
-                    for (i, loc) in alignment
-                        .end_locations
-                        .as_ref()
-                        .expect("No end locations.")
-                        .iter()
-                        .enumerate()
-                    {
-                        if *loc == -1 {
-                            // NOTE: Sometimes one of optimal solutions is that query starts before target, like this:
-                            //                       AAGG <- target
-                            //                   CCTT     <- query
-                            //   It will never be only optimal solution and it does not happen often, however it is
-                            //   possible and in that case end location will be -1. What should we do with that?
-                            //   Should we just skip reporting such end location, although it is a solution?
-                            //   If we do report it, what is the start location? -4? -1? Nothing?
-                            // TODO: Figure this out. This has to do in general with how we think about start
-                            //   and end locations.
-                            //   Also, we have alignment later relying on this locations to limit the space of it's
-                            //   search -> how can it do it right if these locations are negative or incorrect?
-                            // I put 0 for now, but it does not make much sense.
-                            if let Some(start_locs) = alignment.start_locations.as_mut() {
-                                start_locs[i] = 0; // I put 0 for now, but it does not make much sense.
-                            }
-                        } else {
-                            // TODO: Check index.
-                            let rev_target_idx =
-                                transformed_target.len() - usize::try_from(*loc)? - 1;
-                            let mut rev_alignment = Alignment::default();
-                            rev_alignment.calc_edit_dst_semi_global(
+                    for (start_loc, loc) in start_locations.iter_mut().zip(end_locations) {
+                        // NOTE: Sometimes one of optimal solutions is that query starts before target, like this:
+                        //                       AAGG <- target
+                        //                   CCTT     <- query
+                        //   It will never be only optimal solution and it does not happen often, however it is
+                        //   possible and in that case end location will be -1. What should we do with that?
+                        //   Should we just skip reporting such end location, although it is a solution?
+                        //   If we do report it, what is the start location? -4? -1? Nothing?
+                        // TODO: Figure this out. This has to do in general with how we think about start
+                        //   and end locations.
+                        //   Also, we have alignment later relying on this locations to limit the space of it's
+                        //   search -> how can it do it right if these locations are negative or incorrect?
+                        // I put 0 for now, but it does not make much sense.
+                        if let Ok(loc) = usize::try_from(*loc) {
+                            *start_loc = find_start_location(
                                 &rev_peq,
                                 w,
                                 max_num_blocks,
-                                rev_transformed_query.len(),
-                                &[rev_transformed_target[rev_target_idx]],
-                                alignment.edit_distance.unwrap(),
-                                &AlignMode::SHW,
-                            )?;
-
-                            if let (Some(start_locs), Some(rev_last_loc)) = (
-                                alignment.start_locations.as_mut(),
-                                rev_alignment.end_locations.map(|locs| locs[locs.len() - 1]),
-                            ) {
-                                // Taking last location as start ensures that alignment will not start with insertions
-                                // if it can start with mismatches instead.
-                                start_locs[i] = loc - rev_last_loc;
-                            }
-                        }
-                    }
-                } else {
-                    // If mode is SHW or NW
-                    if let Some(start_locs) = alignment.start_locations.as_mut() {
-                        for loc in start_locs.iter_mut() {
-                            *loc = 0
+                                transformed_query.len(),
+                                &transformed_target,
+                                loc,
+                                edit_distance,
+                                &mut meter,
+                            )
+                            .map_err(|err| raise_lower_bound(err, edit_distance))?;
                         }
                     }
                 }
+                alignment.start_locations = Some(start_locations);
             }
-        }
-        // Find alignment -> all comes down to finding alignment for NW.
-        // Currently we return alignment only for first pair of locations.
-        if config.task == AlignTask::Path {
-            let (Some(aln_start_loc), Some(aln_end_loc)) = (
-                alignment
+
+            // Find alignment -> all comes down to finding alignment for NW.
+            // Currently we return alignment only for first pair of locations.
+            if config.task == AlignTask::Path {
+                let start_location = alignment
                     .start_locations
                     .as_ref()
-                    .map(|start_locs| start_locs[0]),
-                alignment.end_locations.as_ref().map(|end_locs| end_locs[0]),
-            ) else {
-                bail!("No start locations.")
-            };
-            let adj_range = usize::try_from(aln_start_loc)?..usize::try_from(aln_end_loc)?;
-            let aln_target = &transformed_target[adj_range];
-            alignment.obtain_optimal_path(&transformed_query, aln_target, &equality_def)?;
+                    .expect("No start locations.")[0];
+                let end_location = alignment.end_locations.as_ref().expect("No end locations.")[0];
+                // End location of -1 means query is aligned before target.
+                let target_end = usize::try_from(end_location + 1)?;
+                let target_start = std::cmp::min(usize::try_from(start_location)?, target_end);
+                alignment
+                    .obtain_optimal_path(
+                        &transformed_query,
+                        &transformed_target[target_start..target_end],
+                        peq,
+                        w,
+                        max_num_blocks,
+                        &equality_def,
+                        &config.traceback,
+                        &mut meter,
+                    )
+                    .map_err(|err| raise_lower_bound(err, edit_distance))?;
+            }
         }
+        alignment.trace = meter.trace.take();
         Ok(alignment)
     }
 
+    /// Find an optimal global path of query and aligned part of target. Edit distance must already be known.
+    /// * Whole band of every column is remembered, so memory is quadratic.
+    /// * Traced back from end, choosing among co-optimal paths by [`TracebackOrder`].
+    ///
+    /// # Arguments
+    /// * `query`: Transformed query.
+    /// * `target`: Transformed target between start and end location.
+    /// * `peq`: Query profile.
+    /// * `w`: Size of padding in last block.
+    /// * `max_num_blocks`: Number of blocks needed to cover the whole query.
+    /// * `equality_def`: Equality of query and target symbols.
+    /// * `order`: [`TracebackOrder`] among co-optimal paths.
+    /// * `meter`: Work counted against [`Budget`](crate::budget::Budget).
+    #[allow(clippy::too_many_arguments)]
     fn obtain_optimal_path(
         &mut self,
         query: &[usize],
         target: &[usize],
+        peq: &[Word],
+        w: usize,
+        max_num_blocks: usize,
         equality_def: &EqualityDefinition,
+        order: &TracebackOrder,
+        meter: &mut Meter,
     ) -> anyhow::Result<()> {
-        // Create reversed copies.
-        let rev_query = query.iter().rev();
-        let rev_target = target.iter().rev();
-
-        // Get alpha len.
-        let alphabet_len = equality_def.alphabet.len();
-
         // Special case.
-        if let Some(empty_seq_op) = if query.is_empty() {
-            Some(EditOp::Delete)
-        } else if target.is_empty() {
-            Some(EditOp::Insert)
-        } else {
-            None
-        } {
-            self.alignment = Some(vec![empty_seq_op; target.len() + query.len()]);
+        if let Some(path) = gap_path(query, target) {
+            self.alignment = Some(path);
             return Ok(());
         }
+        let Some(edit_distance) = self.edit_distance else {
+            bail!("No edit distance to find path of.")
+        };
+
+        let mut align_data = AlignmentData::new(max_num_blocks, target.len());
+        let mut nw_alignment = Alignment::default();
+        meter.quiet(|meter| {
+            nw_alignment.calc_edit_dst_nw(
+                peq,
+                w,
+                max_num_blocks,
+                query.len(),
+                target,
+                edit_distance,
+                &mut None,
+                Some(&mut align_data),
+                None,
+                meter,
+            )
+        })?;
+        if nw_alignment.edit_distance != Some(edit_distance) {
+            bail!("No path with edit distance {edit_distance}.")
+        }
 
-        let word_size = usize::try_from(WORD_SIZE)?;
-        let max_num_blocks = ceil_div!(query.len(), word_size);
-        let w = max_num_blocks * word_size - query.len();
+        // Score of cell before query and target position. Cells larger than edit distance are not correct.
+        let score = |row: usize, column: usize| -> Option<isize> {
+            match (row.checked_sub(1), column.checked_sub(1)) {
+                (None, _) => isize::try_from(column).ok(),
+                (_, None) => isize::try_from(row).ok(),
+                (Some(row), Some(column)) => align_data.cell(max_num_blocks, row, column),
+            }
+            .filter(|score| *score <= edit_distance as isize)
+        };
+        let path = traceback(query, target, equality_def, order, score)?;
+        self.alignment = Some(path);
         Ok(())
     }
-    // pub fn as_cigar(format: CigarFormat) {}
 }
 
+/// Trace back an optimal global path from end of query and target.
+///
+/// # Arguments
+/// * `query`: Transformed query.
+/// * `target`: Transformed target.
+/// * `equality_def`: Equality of query and target symbols.
+/// * `order`: [`TracebackOrder`] among co-optimal paths.
+/// * `score`: Score of cell before query and target position. `None` if not known to be correct.
+///
+/// # Returns
+/// * [`EditOp`]s from start to end of query.
+pub(crate) fn traceback(
+    query: &[usize],
+    target: &[usize],
+    equality_def: &EqualityDefinition,
+    order: &TracebackOrder,
+    score: impl Fn(usize, usize) -> Option<isize>,
+) -> anyhow::Result<Vec<EditOp>> {
+    let mut path = Vec::with_capacity(query.len() + target.len());
+    let (mut row, mut column) = (query.len(), target.len());
+    while row > 0 || column > 0 {
+        let Some(current) = score(row, column) else {
+            bail!("Traceback left band at row {row} and column {column}.")
+        };
+        let diagonal = (row > 0 && column > 0)
+            .then(|| equality_def[(query[row - 1], target[column - 1])])
+            .filter(|is_equal| {
+                score(row - 1, column - 1) == Some(current - isize::from(!is_equal))
+            });
+        let insert = row > 0 && score(row - 1, column) == Some(current - 1);
+        let delete = column > 0 && score(row, column - 1) == Some(current - 1);
+
+        let op = match diagonal {
+            Some(is_equal) if !(insert || delete) || order.diagonal_first(is_equal) => {
+                if is_equal {
+                    EditOp::Match
+                } else {
+                    EditOp::Mismatch
+                }
+            }
+            _ if insert => EditOp::Insert,
+            _ if delete => EditOp::Delete,
+            _ => bail!("No optimal step at row {row} and column {column}."),
+        };
+        match op {
+            EditOp::Match | EditOp::Mismatch => {
+                row -= 1;
+                column -= 1;
+            }
+            EditOp::Insert => row -= 1,
+            EditOp::Delete => column -= 1,
+        }
+        path.push(op);
+    }
+    path.reverse();
+    Ok(path)
+}
+
+/// Path of only insertions or deletions if one of sequences is empty.
+fn gap_path(query: &[usize], target: &[usize]) -> Option<Vec<EditOp>> {
+    if query.is_empty() {
+        Some(vec![EditOp::Delete; target.len()])
+    } else if target.is_empty() {
+        Some(vec![EditOp::Insert; query.len()])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::KGrowth;
 
     #[test]
     fn test_transform_sequences() {
@@ -372,4 +633,38 @@ mod test {
         assert_eq!(transformed_query, EXP_TRANSFORMED_QUERY);
         assert_eq!(transformed_target, EXP_TRANSFORMED_TARGET);
     }
+
+    #[test]
+    fn test_run_k_growth() {
+        let config = AlignConfig {
+            k_growth: KGrowth {
+                start: 2,
+                factor: 3,
+                max: Some(10),
+            },
+            ..Default::default()
+        };
+        let aln = Alignment::run(config.clone(), "ACGTACGTACGT", "ACGAACGAACG").unwrap();
+        assert_eq!(aln.edit_distance, Some(3));
+        assert_eq!(aln.exhausted_k, None);
+
+        let aln = Alignment::run(config, "ACGTACGTACGT", "TTTTTTTTTTTTTTTTTTTT").unwrap();
+        assert_eq!(aln.edit_distance, None);
+        assert_eq!(aln.exhausted_k, Some(10));
+    }
+
+    #[test]
+    fn test_run_k_fraction() {
+        let config = AlignConfig {
+            k_fraction: Some(0.25),
+            mode: AlignMode::HW,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config.clone(), "ACGTACGT", "TTACGAACGTTT").unwrap();
+        assert_eq!(aln.edit_distance, Some(1));
+
+        let aln = Alignment::run(config, "ACGTACGT", "TTAGGAAGGTTT").unwrap();
+        assert_eq!(aln.edit_distance, None);
+        assert_eq!(aln.exhausted_k, Some(2));
+    }
 }
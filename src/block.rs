@@ -1,8 +1,9 @@
-use std::ops::BitAnd;
+//! Bit-vector blocks of Myers' algorithm.
 
 use crate::align::{Word, HIGH_BIT_MASK, WORD_SIZE};
 
 #[derive(Debug, Clone, Default)]
+/// Block of one word of cells within a column of the dynamic programming matrix.
 pub struct Block {
     /// Bit vector Pvin
     pub p: Word,
@@ -90,6 +91,20 @@ impl Block {
         scores
     }
 
+    /// Get score value of a single cell in `Block`.
+    /// * Cheaper than [`Block::get_cell_values`] as only bits between the last cell and the cell are counted.
+    ///
+    /// # Arguments
+    /// * `offset`: Number of cells above the last (most bottom) cell of block. Must be less than [`WORD_SIZE`].
+    ///
+    /// # Returns
+    /// * Score of cell.
+    pub fn get_cell_value(&self, offset: usize) -> isize {
+        // Bits of pvin and mvin of cells between bottom cell and cell. i.e. 111..00 for offset of 3.
+        let mask = !(Word::MAX >> offset);
+        self.score - (self.p & mask).count_ones() as isize + (self.m & mask).count_ones() as isize
+    }
+
     /// Check if all block cell values are larger than `k`.
     /// # Arguments
     /// * `k`: Threshold value to compare values.
@@ -0,0 +1,224 @@
+//! Cheap lower and upper bounds of edit distance.
+//!
+//! Bounds take either ASCII bytes or transformed sequences and only use exact equality of symbols.
+//! * Upper bounds stay valid with added equalities, as they can only lower the edit distance.
+//! * [`histogram_lower_bound`] is not valid with added equalities.
+
+use crate::mode::AlignMode;
+
+/// Lower bound of edit distance from sequence lengths.
+/// * Query characters not aligned to target cost an indel each.
+///
+/// # Arguments
+/// * `query_len`: Length of query.
+/// * `target_len`: Length of target.
+/// * `mode`: [`AlignMode`]. Only [`AlignMode::NW`] penalizes target characters not aligned to query.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{bounds::length_lower_bound, mode::AlignMode};
+///
+/// assert_eq!(length_lower_bound(4, 10, &AlignMode::NW), 6);
+/// assert_eq!(length_lower_bound(4, 10, &AlignMode::HW), 0);
+/// ```
+pub fn length_lower_bound(query_len: usize, target_len: usize, mode: &AlignMode) -> usize {
+    match mode {
+        AlignMode::NW => query_len.abs_diff(target_len),
+        AlignMode::SHW | AlignMode::HW => query_len.saturating_sub(target_len),
+    }
+}
+
+/// Lower bound of edit distance from symbol counts.
+/// * Each query symbol occurring more often in query than in target needs an edit per surplus occurrence.
+/// * For [`AlignMode::NW`], the same holds for target symbols, and a mismatch removes one surplus of each.
+/// * Never smaller than [`length_lower_bound`].
+///
+/// # Arguments
+/// * `query`: Query symbols.
+/// * `target`: Target symbols.
+/// * `mode`: [`AlignMode`].
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{bounds::histogram_lower_bound, mode::AlignMode};
+///
+/// // Same length, but two A's of query are missing in target.
+/// assert_eq!(histogram_lower_bound(b"AAGT", b"TTGT", &AlignMode::NW), 2);
+/// ```
+pub fn histogram_lower_bound<T: Copy + Into<usize>>(
+    query: &[T],
+    target: &[T],
+    mode: &AlignMode,
+) -> usize {
+    let num_symbols = query
+        .iter()
+        .chain(target)
+        .map(|symbol| (*symbol).into() + 1)
+        .max()
+        .unwrap_or(0);
+    // Query count minus target count of each symbol.
+    let mut count_diffs = vec![0isize; num_symbols];
+    for symbol in query {
+        count_diffs[(*symbol).into()] += 1;
+    }
+    for symbol in target {
+        count_diffs[(*symbol).into()] -= 1;
+    }
+    let query_surplus: usize = count_diffs
+        .iter()
+        .filter(|diff| **diff > 0)
+        .map(|diff| diff.unsigned_abs())
+        .sum();
+    let target_surplus: usize = count_diffs
+        .iter()
+        .filter(|diff| **diff < 0)
+        .map(|diff| diff.unsigned_abs())
+        .sum();
+
+    match mode {
+        AlignMode::NW => std::cmp::max(query_surplus, target_surplus),
+        AlignMode::SHW | AlignMode::HW => query_surplus,
+    }
+}
+
+/// Upper bound of edit distance from aligning query and target without gaps from their start.
+/// * Mismatches along main diagonal plus characters past end of shorter sequence that are penalized.
+/// * For [`AlignMode::SHW`] and [`AlignMode::HW`], never larger than query length.
+///
+/// # Arguments
+/// * `query`: Query symbols.
+/// * `target`: Target symbols.
+/// * `mode`: [`AlignMode`].
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{bounds::hamming_upper_bound, mode::AlignMode};
+///
+/// assert_eq!(hamming_upper_bound(b"ACGT", b"ACCTAA", &AlignMode::NW), 3);
+/// assert_eq!(hamming_upper_bound(b"ACGT", b"ACCTAA", &AlignMode::SHW), 1);
+/// ```
+pub fn hamming_upper_bound<T: Eq>(query: &[T], target: &[T], mode: &AlignMode) -> usize {
+    let mismatches = query
+        .iter()
+        .zip(target)
+        .filter(|(query_symbol, target_symbol)| query_symbol != target_symbol)
+        .count();
+    let bound = mismatches + length_lower_bound(query.len(), target.len(), mode);
+    cap_upper_bound(bound, query.len(), mode)
+}
+
+/// Upper bound of edit distance from a greedy walk along the diagonal from start of query and target.
+/// * Matches are taken whenever possible.
+/// * On a mismatch, a single indel is taken if it leads to a match, otherwise a mismatch.
+/// * Tolerates shifts by indels, unlike [`hamming_upper_bound`].
+///
+/// # Arguments
+/// * `query`: Query symbols.
+/// * `target`: Target symbols.
+/// * `mode`: [`AlignMode`].
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{bounds::greedy_upper_bound, mode::AlignMode};
+///
+/// // Single deletion shifts rest of query.
+/// assert_eq!(greedy_upper_bound(b"ACGTACGT", b"ACTACGT", &AlignMode::NW), 1);
+/// ```
+pub fn greedy_upper_bound<T: Eq>(query: &[T], target: &[T], mode: &AlignMode) -> usize {
+    let (mut i, mut j, mut cost) = (0, 0, 0);
+    while i < query.len() && j < target.len() {
+        if query[i] == target[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        cost += 1;
+        if query.get(i + 1) == Some(&target[j]) {
+            // Query character not in target.
+            i += 1;
+        } else if target.get(j + 1) == Some(&query[i]) {
+            // Target character not in query.
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    let bound = cost + length_lower_bound(query.len() - i, target.len() - j, mode);
+    cap_upper_bound(bound, query.len(), mode)
+}
+
+/// Best lower bound of edit distance.
+///
+/// # Arguments
+/// * `query`: Query symbols.
+/// * `target`: Target symbols.
+/// * `mode`: [`AlignMode`].
+pub fn lower_bound<T: Copy + Into<usize>>(query: &[T], target: &[T], mode: &AlignMode) -> usize {
+    std::cmp::max(
+        length_lower_bound(query.len(), target.len(), mode),
+        histogram_lower_bound(query, target, mode),
+    )
+}
+
+/// Best upper bound of edit distance.
+///
+/// # Arguments
+/// * `query`: Query symbols.
+/// * `target`: Target symbols.
+/// * `mode`: [`AlignMode`].
+pub fn upper_bound<T: Eq>(query: &[T], target: &[T], mode: &AlignMode) -> usize {
+    std::cmp::min(
+        hamming_upper_bound(query, target, mode),
+        greedy_upper_bound(query, target, mode),
+    )
+}
+
+/// Semi-global alignments can always delete whole query.
+fn cap_upper_bound(bound: usize, query_len: usize, mode: &AlignMode) -> usize {
+    match mode {
+        AlignMode::NW => bound,
+        AlignMode::SHW | AlignMode::HW => std::cmp::min(bound, query_len),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{align::Alignment, config::AlignConfig};
+
+    #[test]
+    fn test_bounds_contain_edit_distance() {
+        let pairs = [
+            ("ACGTACGT", "ACGTACGT"),
+            ("ACGTACGT", "TACGTACG"),
+            ("AAAA", "CCCCCCCC"),
+            ("GATTACA", "GCATGCT"),
+            ("ACGT", "TTTTACGTTTTT"),
+            ("ACGTTGCA", "AC"),
+        ];
+        for mode in [AlignMode::NW, AlignMode::SHW, AlignMode::HW] {
+            for (query, target) in pairs {
+                let config = AlignConfig {
+                    mode: mode.clone(),
+                    ..Default::default()
+                };
+                let edit_distance = Alignment::run(config, query, target)
+                    .unwrap()
+                    .edit_distance
+                    .unwrap();
+                let (query, target) = (query.as_bytes(), target.as_bytes());
+                assert!(lower_bound(query, target, &mode) <= edit_distance);
+                assert!(upper_bound(query, target, &mode) >= edit_distance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_upper_bound_shift() {
+        // Hamming distance is large after shift by insertion.
+        let (query, target) = (b"TACGTACGTACG", b"TTACGTACGTACG");
+        assert_eq!(hamming_upper_bound(query, target, &AlignMode::NW), 12);
+        assert_eq!(greedy_upper_bound(query, target, &AlignMode::NW), 1);
+    }
+}
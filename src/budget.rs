@@ -0,0 +1,298 @@
+//! Limits on the work of an alignment.
+
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use crate::{
+    column::ColumnState,
+    progress::{Progress, ProgressReport},
+    trace::Trace,
+};
+
+/// Number of columns between checks of cancellation flag and deadline.
+pub const CHECK_INTERVAL: usize = 256;
+
+/// Limits on the work of [`Alignment::run`](crate::align::Alignment::run).
+/// * Work is counted in calculated [`Block`](crate::block::Block)s of 64 cells, summed over all retries with a larger `k`.
+/// * Cancellation flag and deadline are checked every [`CHECK_INTERVAL`] columns.
+/// * If a limit is reached, alignment fails with an [`Interrupted`] error.
+///
+/// ### Example
+/// ```
+/// use std::sync::{atomic::AtomicBool, Arc};
+///
+/// use rs_edlib::{
+///     align::Alignment,
+///     budget::{Budget, InterruptReason, Interrupted},
+///     config::AlignConfig,
+/// };
+///
+/// let config = AlignConfig {
+///     budget: Budget {
+///         cancel: Some(Arc::new(AtomicBool::new(true))),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// let err = Alignment::run(config, "ACGT", "ACCT").unwrap_err();
+/// let interrupted = err.downcast_ref::<Interrupted>().unwrap();
+/// assert_eq!(interrupted.reason, InterruptReason::Cancelled);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    /// Flag set by another thread to cancel alignment.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Maximum number of calculated blocks.
+    pub max_blocks: Option<usize>,
+    /// Time after which alignment is given up.
+    pub deadline: Option<Instant>,
+}
+
+/// Why an alignment was interrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptReason {
+    /// Cancellation flag was set.
+    Cancelled,
+    /// Maximum number of blocks was calculated.
+    BlockBudget,
+    /// Deadline passed.
+    Deadline,
+}
+
+/// Error of an alignment interrupted by its [`Budget`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interrupted {
+    /// Limit that was reached.
+    pub reason: InterruptReason,
+    /// Number of blocks calculated before interruption.
+    pub num_blocks: usize,
+    /// Lower bound of edit distance known before interruption.
+    /// * From [`bounds`](crate::bounds) and retries with too small `k`.
+    pub lower_bound: usize,
+}
+
+impl Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.reason {
+            InterruptReason::Cancelled => "cancelled",
+            InterruptReason::BlockBudget => "out of block budget",
+            InterruptReason::Deadline => "past deadline",
+        };
+        write!(
+            f,
+            "Alignment {reason} after {} blocks. Edit distance is at least {}.",
+            self.num_blocks, self.lower_bound
+        )
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Counter of work against a [`Budget`], reporter of [`Progress`] and recorder of [`Trace`].
+/// * Default is unlimited and silent.
+#[derive(Debug, Clone, Default)]
+pub struct Meter {
+    budget: Budget,
+    pub(crate) progress: Option<Progress>,
+    pub(crate) trace: Option<Trace>,
+    num_blocks: usize,
+    num_columns: usize,
+}
+
+impl Meter {
+    /// Initialize a new `Meter` with no work counted.
+    pub fn new(budget: Budget, progress: Option<Progress>) -> Self {
+        Meter {
+            budget,
+            progress,
+            trace: None,
+            num_blocks: 0,
+            num_columns: 0,
+        }
+    }
+
+    /// Count one calculated column.
+    ///
+    /// # Arguments
+    /// * `num_blocks`: Number of blocks calculated in column.
+    ///
+    /// # Returns
+    /// * [`Interrupted`] error if a limit is reached.
+    pub fn charge(&mut self, num_blocks: usize) -> anyhow::Result<()> {
+        self.num_blocks += num_blocks;
+        if self
+            .budget
+            .max_blocks
+            .is_some_and(|max_blocks| self.num_blocks > max_blocks)
+        {
+            return Err(self.interrupted(InterruptReason::BlockBudget).into());
+        }
+
+        // Checking time is comparatively slow.
+        let check = self.num_columns.is_multiple_of(CHECK_INTERVAL);
+        self.num_columns += 1;
+        if !check {
+            return Ok(());
+        }
+        if self
+            .budget
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            return Err(self.interrupted(InterruptReason::Cancelled).into());
+        }
+        if self
+            .budget
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(self.interrupted(InterruptReason::Deadline).into());
+        }
+        Ok(())
+    }
+
+    /// Report progress if column is at end of an interval.
+    ///
+    /// # Arguments
+    /// * `column`: Zero-based index of calculated target column.
+    /// * `state`: State of column.
+    /// * `best_score`: Best score so far. Only called if reported.
+    #[inline]
+    pub(crate) fn report(
+        &self,
+        column: usize,
+        state: &ColumnState,
+        best_score: impl FnOnce() -> Option<usize>,
+    ) {
+        let Some(progress) = &self.progress else {
+            return;
+        };
+        if !(column + 1).is_multiple_of(progress.interval) {
+            return;
+        }
+        (progress.callback)(&ProgressReport {
+            column,
+            band: (!state.is_empty()).then_some((
+                state.first_block_idx as usize,
+                state.last_block_idx as usize,
+            )),
+            best_score: best_score(),
+        })
+    }
+
+    /// Record column in trace, if tracing.
+    ///
+    /// # Arguments
+    /// * `column`: Zero-based index of calculated target column.
+    /// * `state`: State of column.
+    #[inline]
+    pub(crate) fn record(&mut self, column: usize, state: &ColumnState) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.record(column, state)
+        }
+    }
+
+    /// Run a calculation counted against budget without reporting progress or recording trace.
+    /// * Progress and trace are only for search of end locations.
+    ///
+    /// # Arguments
+    /// * `calculation`: Calculation given this meter.
+    pub(crate) fn quiet<T>(&mut self, calculation: impl FnOnce(&mut Meter) -> T) -> T {
+        let progress = self.progress.take();
+        let trace = self.trace.take();
+        let result = calculation(self);
+        self.progress = progress;
+        self.trace = trace;
+        result
+    }
+
+    /// Number of blocks counted.
+    pub fn num_blocks(&self) -> usize {
+        self.num_blocks
+    }
+
+    fn interrupted(&self, reason: InterruptReason) -> Interrupted {
+        Interrupted {
+            reason,
+            num_blocks: self.num_blocks,
+            lower_bound: 0,
+        }
+    }
+}
+
+/// Raise lower bound of an [`Interrupted`] error. Other errors are returned unchanged.
+pub(crate) fn raise_lower_bound(err: anyhow::Error, lower_bound: usize) -> anyhow::Error {
+    match err.downcast::<Interrupted>() {
+        Ok(mut interrupted) => {
+            interrupted.lower_bound = std::cmp::max(interrupted.lower_bound, lower_bound);
+            interrupted.into()
+        }
+        Err(err) => err,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{align::Alignment, config::AlignConfig, mode::AlignMode};
+
+    fn interrupted(budget: Budget, mode: AlignMode) -> Interrupted {
+        let config = AlignConfig {
+            mode,
+            budget,
+            ..Default::default()
+        };
+        let query = str::repeat("ACGT", 100);
+        // Lower bound from length difference.
+        let target = str::repeat("TGCA", 120);
+        let err = Alignment::run(config, query, target).unwrap_err();
+        err.downcast::<Interrupted>().unwrap()
+    }
+
+    #[test]
+    fn test_budget_max_blocks() {
+        let budget = Budget {
+            max_blocks: Some(100),
+            ..Default::default()
+        };
+        for mode in [AlignMode::NW, AlignMode::HW] {
+            let interrupted = interrupted(budget.clone(), mode);
+            assert_eq!(interrupted.reason, InterruptReason::BlockBudget);
+            assert!(interrupted.num_blocks > 100);
+        }
+        assert_eq!(interrupted(budget, AlignMode::NW).lower_bound, 80);
+    }
+
+    #[test]
+    fn test_budget_deadline() {
+        let budget = Budget {
+            deadline: Instant::now().checked_sub(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let interrupted = interrupted(budget, AlignMode::SHW);
+        assert_eq!(interrupted.reason, InterruptReason::Deadline);
+    }
+
+    #[test]
+    fn test_budget_unlimited() {
+        let config = AlignConfig {
+            budget: Budget {
+                cancel: Some(Arc::new(AtomicBool::new(false))),
+                max_blocks: Some(1000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, "ACGTACGT", "ACGAACGT").unwrap();
+        assert_eq!(aln.edit_distance, Some(1));
+    }
+}
@@ -1,29 +1,542 @@
+//! Alignment operations and CIGAR formats.
+
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context};
+
+use crate::{align::Alignment, equal::EqualityPair};
+
 /// Describes CIGAR format.
 /// * See http://samtools.github.io/hts-specs/SAMv1.pdf
 /// * See http://drive5.com/usearch/manual/cigar.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum CigarFormat {
     /// Match: 'M', Insertion: 'I', Deletion: 'D', Mismatch: 'M'.
     Standard,
+    #[default]
     /// Match: '=', Insertion: 'I', Deletion: 'D', Mismatch: 'X'.
     Extended,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Edit operation of an alignment.
 pub enum EditOp {
     /// Match
     Match,
-    /// Insertion to target = deletion from query.
+    /// Insertion to target = deletion from query. Consumes a query symbol.
     ///
     /// ### Example:
-    /// * Target: `ATCG`
-    /// * Query:  `A-CG`
+    /// * Target: `A-CG`
+    /// * Query:  `ATCG`
     Insert,
-    /// Deletion from target = insertion to query.
+    /// Deletion from target = insertion to query. Consumes a target symbol.
     ///
     /// ### Example:
-    /// * Target: `A-CG`
-    /// * Query:  `ATCG`
+    /// * Target: `ATCG`
+    /// * Query:  `A-CG`
     Delete,
     /// Mismatch
     Mismatch,
 }
+
+/// Operation of a CIGAR run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CigarOp {
+    /// `M`: Match or mismatch.
+    AlignmentMatch,
+    /// `I`: [`EditOp::Insert`].
+    Insert,
+    /// `D`: [`EditOp::Delete`].
+    Delete,
+    /// `=`: [`EditOp::Match`].
+    Match,
+    /// `X`: [`EditOp::Mismatch`].
+    Mismatch,
+}
+
+impl CigarOp {
+    /// Character of operation in CIGAR string.
+    pub fn symbol(&self) -> char {
+        match self {
+            CigarOp::AlignmentMatch => 'M',
+            CigarOp::Insert => 'I',
+            CigarOp::Delete => 'D',
+            CigarOp::Match => '=',
+            CigarOp::Mismatch => 'X',
+        }
+    }
+
+    /// Operation of character in CIGAR string.
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            'M' => Some(CigarOp::AlignmentMatch),
+            'I' => Some(CigarOp::Insert),
+            'D' => Some(CigarOp::Delete),
+            '=' => Some(CigarOp::Match),
+            'X' => Some(CigarOp::Mismatch),
+            _ => None,
+        }
+    }
+
+    /// Code of operation in BAM encoding.
+    pub fn bam_code(&self) -> u32 {
+        match self {
+            CigarOp::AlignmentMatch => 0,
+            CigarOp::Insert => 1,
+            CigarOp::Delete => 2,
+            CigarOp::Match => 7,
+            CigarOp::Mismatch => 8,
+        }
+    }
+
+    /// Operation of code in BAM encoding.
+    pub fn from_bam_code(code: u32) -> Option<Self> {
+        match code {
+            0 => Some(CigarOp::AlignmentMatch),
+            1 => Some(CigarOp::Insert),
+            2 => Some(CigarOp::Delete),
+            7 => Some(CigarOp::Match),
+            8 => Some(CigarOp::Mismatch),
+            _ => None,
+        }
+    }
+
+    /// Operation of an [`EditOp`] in given format.
+    pub fn from_edit_op(op: EditOp, format: CigarFormat) -> Self {
+        match (op, format) {
+            (EditOp::Insert, _) => CigarOp::Insert,
+            (EditOp::Delete, _) => CigarOp::Delete,
+            (EditOp::Match | EditOp::Mismatch, CigarFormat::Standard) => CigarOp::AlignmentMatch,
+            (EditOp::Match, CigarFormat::Extended) => CigarOp::Match,
+            (EditOp::Mismatch, CigarFormat::Extended) => CigarOp::Mismatch,
+        }
+    }
+
+    /// If operation consumes a query symbol.
+    pub fn consumes_query(&self) -> bool {
+        *self != CigarOp::Delete
+    }
+
+    /// If operation consumes a target symbol.
+    pub fn consumes_target(&self) -> bool {
+        *self != CigarOp::Insert
+    }
+}
+
+/// Maximum length of a run in BAM encoding.
+pub const MAX_BAM_RUN_LEN: usize = (1 << 28) - 1;
+
+/// Run-length encoded alignment path.
+/// * Query is read and target is reference in terms of SAM.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::cigar::{Cigar, CigarFormat, EditOp};
+///
+/// let cigar: Cigar = "2=1I1X".parse().unwrap();
+/// assert_eq!(
+///     cigar.to_path().unwrap(),
+///     [EditOp::Match, EditOp::Match, EditOp::Insert, EditOp::Mismatch]
+/// );
+/// assert_eq!(cigar.to_format(CigarFormat::Standard).to_string(), "2M1I1M");
+/// cigar.validate("ACGT", "ACA", &[], Some(2)).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Cigar {
+    /// Length and operation of each run. Adjacent runs have different operations.
+    pub runs: Vec<(usize, CigarOp)>,
+}
+
+impl Cigar {
+    /// Run-length encode a path.
+    ///
+    /// # Arguments
+    /// * `path`: [`EditOp`]s from start to end of alignment.
+    /// * `format`: [`CigarFormat`] of operations.
+    pub fn from_path(path: &[EditOp], format: CigarFormat) -> Self {
+        let mut cigar = Cigar::default();
+        for op in path {
+            cigar.push(1, CigarOp::from_edit_op(*op, format));
+        }
+        cigar
+    }
+
+    /// Add a run, merging it with last run if they have the same operation. Empty runs are skipped.
+    pub fn push(&mut self, len: usize, op: CigarOp) {
+        if len == 0 {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some((last_len, last_op)) if *last_op == op => *last_len += len,
+            _ => self.runs.push((len, op)),
+        }
+    }
+
+    /// Path of extended CIGAR.
+    ///
+    /// # Returns
+    /// * [`EditOp`]s from start to end of alignment.
+    /// * Error if CIGAR has an `M` that could be a match or mismatch. See [`Cigar::resolve`].
+    pub fn to_path(&self) -> anyhow::Result<Vec<EditOp>> {
+        let mut path = Vec::with_capacity(self.runs.iter().map(|(len, _)| len).sum());
+        for (len, op) in self.runs.iter() {
+            let edit_op = match op {
+                CigarOp::AlignmentMatch => {
+                    bail!("Cannot tell match from mismatch of M without sequences.")
+                }
+                CigarOp::Insert => EditOp::Insert,
+                CigarOp::Delete => EditOp::Delete,
+                CigarOp::Match => EditOp::Match,
+                CigarOp::Mismatch => EditOp::Mismatch,
+            };
+            path.extend(std::iter::repeat_n(edit_op, *len));
+        }
+        Ok(path)
+    }
+
+    /// CIGAR in another format. Runs of `M` can only be converted to extended format with [`Cigar::resolve`].
+    pub fn to_format(&self, format: CigarFormat) -> Self {
+        let mut cigar = Cigar::default();
+        for (len, op) in self.runs.iter() {
+            let op = match (op, format) {
+                (CigarOp::Match | CigarOp::Mismatch, CigarFormat::Standard) => {
+                    CigarOp::AlignmentMatch
+                }
+                _ => *op,
+            };
+            cigar.push(*len, op);
+        }
+        cigar
+    }
+
+    /// Path of CIGAR, comparing sequences to tell matches from mismatches of `M`.
+    ///
+    /// # Arguments
+    /// * `query`: Aligned query.
+    /// * `target`: Aligned part of target.
+    /// * `added_equalities`: Pairs of query and target symbols that are also equal.
+    ///
+    /// # Returns
+    /// * [`EditOp`]s from start to end of alignment.
+    /// * Error if CIGAR does not consume both sequences exactly, or if `=` or `X` disagree with sequences.
+    pub fn resolve(
+        &self,
+        query: &str,
+        target: &str,
+        added_equalities: &[EqualityPair],
+    ) -> anyhow::Result<Vec<EditOp>> {
+        let (query_len, target_len) = (self.query_len(), self.target_len());
+        if (query_len, target_len) != (query.chars().count(), target.chars().count()) {
+            bail!(
+                "CIGAR {self} spans {query_len} query and {target_len} target symbols, not {} and {}.",
+                query.chars().count(),
+                target.chars().count()
+            )
+        }
+        let is_equal = |q: char, t: char| {
+            q == t
+                || added_equalities
+                    .iter()
+                    .any(|pair| pair.first == q && pair.second == t)
+        };
+
+        let (mut query_chars, mut target_chars) = (query.chars(), target.chars());
+        let mut path = Vec::with_capacity(self.runs.iter().map(|(len, _)| len).sum());
+        for (len, op) in self.runs.iter() {
+            for _ in 0..*len {
+                let edit_op = match op {
+                    CigarOp::Insert => {
+                        query_chars.next();
+                        EditOp::Insert
+                    }
+                    CigarOp::Delete => {
+                        target_chars.next();
+                        EditOp::Delete
+                    }
+                    CigarOp::AlignmentMatch | CigarOp::Match | CigarOp::Mismatch => {
+                        let (Some(q), Some(t)) = (query_chars.next(), target_chars.next()) else {
+                            unreachable!("Lengths are checked.")
+                        };
+                        let edit_op = if is_equal(q, t) {
+                            EditOp::Match
+                        } else {
+                            EditOp::Mismatch
+                        };
+                        if *op != CigarOp::AlignmentMatch
+                            && *op != CigarOp::from_edit_op(edit_op, CigarFormat::Extended)
+                        {
+                            bail!(
+                                "{} at query position {} does not agree with {q} and {t}.",
+                                op.symbol(),
+                                path.iter().filter(|op| **op != EditOp::Delete).count()
+                            )
+                        }
+                        edit_op
+                    }
+                };
+                path.push(edit_op);
+            }
+        }
+        Ok(path)
+    }
+
+    /// Check that CIGAR is a consistent alignment of query and target.
+    ///
+    /// # Arguments
+    /// * `query`: Aligned query.
+    /// * `target`: Aligned part of target.
+    /// * `added_equalities`: Pairs of query and target symbols that are also equal.
+    /// * `edit_distance`: Expected number of edits, if any.
+    ///
+    /// # Returns
+    /// * Error describing first inconsistency.
+    pub fn validate(
+        &self,
+        query: &str,
+        target: &str,
+        added_equalities: &[EqualityPair],
+        edit_distance: Option<usize>,
+    ) -> anyhow::Result<()> {
+        let path = self.resolve(query, target, added_equalities)?;
+        let num_edits = path.iter().filter(|op| **op != EditOp::Match).count();
+        if let Some(edit_distance) = edit_distance.filter(|dst| *dst != num_edits) {
+            bail!("CIGAR {self} has {num_edits} edits, not edit distance {edit_distance}.")
+        }
+        Ok(())
+    }
+
+    /// Reconstruct query from target and CIGAR.
+    /// * Symbols of `=` and `M` are copied from target. `M` is assumed to be a match.
+    /// * Symbols of `X` and `I` are not known and replaced by `unknown`.
+    ///
+    /// # Arguments
+    /// * `target`: Aligned part of target.
+    /// * `unknown`: Symbol of unknown query symbols, like `N`.
+    ///
+    /// # Returns
+    /// * Query. Error if CIGAR does not consume target exactly.
+    pub fn apply(&self, target: &str, unknown: char) -> anyhow::Result<String> {
+        let mut target_chars = target.chars();
+        let mut query = String::with_capacity(self.query_len());
+        for (len, op) in self.runs.iter() {
+            for _ in 0..*len {
+                let target_char = if op.consumes_target() {
+                    Some(
+                        target_chars
+                            .next()
+                            .context("CIGAR is longer than target.")?,
+                    )
+                } else {
+                    None
+                };
+                match (op, target_char) {
+                    (CigarOp::Match | CigarOp::AlignmentMatch, Some(target_char)) => {
+                        query.push(target_char)
+                    }
+                    (CigarOp::Mismatch | CigarOp::Insert, _) => query.push(unknown),
+                    _ => (),
+                }
+            }
+        }
+        if target_chars.next().is_some() {
+            bail!("CIGAR is shorter than target.")
+        }
+        Ok(query)
+    }
+
+    /// Number of query symbols of alignment.
+    pub fn query_len(&self) -> usize {
+        self.runs
+            .iter()
+            .filter(|(_, op)| op.consumes_query())
+            .map(|(len, _)| len)
+            .sum()
+    }
+
+    /// Number of target symbols of alignment.
+    pub fn target_len(&self) -> usize {
+        self.runs
+            .iter()
+            .filter(|(_, op)| op.consumes_target())
+            .map(|(len, _)| len)
+            .sum()
+    }
+
+    /// Pack into BAM encoding of `len << 4 | op`.
+    /// * Error if a run is longer than [`MAX_BAM_RUN_LEN`].
+    pub fn to_bam(&self) -> anyhow::Result<Vec<u32>> {
+        self.runs
+            .iter()
+            .map(|(len, op)| {
+                if *len > MAX_BAM_RUN_LEN {
+                    bail!("Run of {len} is too long for BAM.")
+                }
+                Ok((*len as u32) << 4 | op.bam_code())
+            })
+            .collect()
+    }
+
+    /// Unpack from BAM encoding of `len << 4 | op`.
+    /// * Error if an operation is unsupported, like soft clipping.
+    pub fn from_bam(encoded: &[u32]) -> anyhow::Result<Self> {
+        let mut cigar = Cigar::default();
+        for code in encoded {
+            let Some(op) = CigarOp::from_bam_code(code & 0xF) else {
+                bail!("Unsupported BAM CIGAR operation {}.", code & 0xF)
+            };
+            cigar.push(usize::try_from(code >> 4)?, op);
+        }
+        Ok(cigar)
+    }
+}
+
+impl FromStr for Cigar {
+    type Err = anyhow::Error;
+
+    /// Parse a standard or extended CIGAR string, like `3M1I2D` or `3=1I2D`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cigar = Cigar::default();
+        let mut len: Option<usize> = None;
+        for chr in s.chars() {
+            if let Some(digit) = chr.to_digit(10) {
+                len = Some(
+                    len.unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|len| len.checked_add(digit as usize))
+                        .with_context(|| format!("Run length overflows in CIGAR {s}."))?,
+                );
+                continue;
+            }
+            let Some(op) = CigarOp::from_symbol(chr) else {
+                bail!("Invalid operation {chr} in CIGAR {s}.")
+            };
+            match len.take() {
+                Some(0) | None => bail!("Missing run length before {chr} in CIGAR {s}."),
+                Some(len) => cigar.push(len, op),
+            }
+        }
+        if len.is_some() {
+            bail!("Missing operation at end of CIGAR {s}.")
+        }
+        Ok(cigar)
+    }
+}
+
+impl Display for Cigar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (len, op) in self.runs.iter() {
+            write!(f, "{len}{}", op.symbol())?;
+        }
+        Ok(())
+    }
+}
+
+impl Alignment {
+    /// CIGAR of alignment path, if found with [`AlignTask::Path`](crate::task::AlignTask::Path).
+    ///
+    /// # Arguments
+    /// * `format`: [`CigarFormat`] of operations.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, cigar::CigarFormat, config::AlignConfig, task::AlignTask};
+    ///
+    /// let config = AlignConfig {
+    ///     task: AlignTask::Path,
+    ///     ..Default::default()
+    /// };
+    /// let aln = Alignment::run(config, "ACGT", "AGT").unwrap();
+    /// let cigar = aln.cigar(CigarFormat::Extended).unwrap();
+    /// assert_eq!(cigar.to_string(), "1=1I2=");
+    /// ```
+    pub fn cigar(&self, format: CigarFormat) -> Option<Cigar> {
+        self.alignment
+            .as_ref()
+            .map(|path| Cigar::from_path(path, format))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cigar_parse_and_display() {
+        let cigar: Cigar = "10M2I1D3=1X".parse().unwrap();
+        assert_eq!(
+            cigar.runs,
+            [
+                (10, CigarOp::AlignmentMatch),
+                (2, CigarOp::Insert),
+                (1, CigarOp::Delete),
+                (3, CigarOp::Match),
+                (1, CigarOp::Mismatch)
+            ]
+        );
+        assert_eq!(cigar.to_string(), "10M2I1D3=1X");
+        assert_eq!((cigar.query_len(), cigar.target_len()), (16, 15));
+        // Adjacent runs are merged.
+        assert_eq!("1=2=".parse::<Cigar>().unwrap().to_string(), "3=");
+
+        for invalid in ["M", "3", "0M", "2S", "1M2"] {
+            assert!(invalid.parse::<Cigar>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_cigar_path_round_trip() {
+        let path = [
+            EditOp::Match,
+            EditOp::Match,
+            EditOp::Delete,
+            EditOp::Mismatch,
+            EditOp::Insert,
+            EditOp::Insert,
+        ];
+        let cigar = Cigar::from_path(&path, CigarFormat::Extended);
+        assert_eq!(cigar.to_string(), "2=1D1X2I");
+        assert_eq!(cigar.to_path().unwrap(), path);
+
+        let standard = Cigar::from_path(&path, CigarFormat::Standard);
+        assert_eq!(standard.to_string(), "2M1D1M2I");
+        assert!(standard.to_path().is_err());
+        assert_eq!(standard.resolve("ACTGG", "ACGG", &[]).unwrap(), path);
+    }
+
+    #[test]
+    fn test_cigar_bam() {
+        let cigar: Cigar = "3M1I2D4=1X".parse().unwrap();
+        let encoded = cigar.to_bam().unwrap();
+        assert_eq!(encoded[0], 3 << 4);
+        assert_eq!(encoded[3], 4 << 4 | 7);
+        assert_eq!(Cigar::from_bam(&encoded).unwrap(), cigar);
+        // Soft clip.
+        assert!(Cigar::from_bam(&[4 << 4 | 4]).is_err());
+    }
+
+    #[test]
+    fn test_cigar_validate_and_apply() {
+        let cigar: Cigar = "2=1I1X1D".parse().unwrap();
+        cigar.validate("ACGT", "ACAA", &[], Some(3)).unwrap();
+        // Wrong edit distance.
+        assert!(cigar.validate("ACGT", "ACAA", &[], Some(2)).is_err());
+        // = disagrees with sequences.
+        assert!(cigar.validate("AGGT", "ACAA", &[], None).is_err());
+        // Lengths don't add up.
+        assert!(cigar.validate("ACGT", "ACA", &[], None).is_err());
+        // Added equality of query T and target A.
+        let equality = [EqualityPair {
+            first: 'T',
+            second: 'A',
+        }];
+        assert!(cigar.validate("ACGT", "ACAA", &equality, None).is_err());
+        "2=1I1=1D"
+            .parse::<Cigar>()
+            .unwrap()
+            .validate("ACGT", "ACAA", &equality, Some(2))
+            .unwrap();
+
+        assert_eq!(cigar.apply("ACAA", 'N').unwrap(), "ACNN");
+        assert!(cigar.apply("ACA", 'N').is_err());
+    }
+}
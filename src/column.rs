@@ -0,0 +1,335 @@
+//! Column-by-column calculation of Myers' bit-vector algorithm.
+
+use anyhow::bail;
+
+use crate::{
+    align::{Word, WORD_1, WORD_SIZE},
+    block::Block,
+    ceil_div,
+    dst::STRONG_REDUCE_NUM,
+    mode::AlignMode,
+};
+
+/// State of the Ukkonen band of one column of the dynamic programming matrix.
+/// * Rows are query and columns are target.
+/// * Query is padded at the end with `w` wildcard cells to fill last block.
+/// * Band is reduced like in the semi-global method. Cells larger than `k` are not guaranteed to be correct.
+/// * If target length is known, like in [`AlignMode::NW`], band is also bounded by the diagonal through the last cell,
+///   and `k` is lowered by the scores of calculated columns.
+#[derive(Debug, Clone)]
+pub(crate) struct ColumnState {
+    /// Blocks of column. Only blocks within band are valid.
+    pub(crate) blocks: Vec<Block>,
+    /// 0-based index of first block in Ukkonen band.
+    pub(crate) first_block_idx: isize,
+    /// 0-based index of last block in Ukkonen band. `-1` if band stopped existing.
+    pub(crate) last_block_idx: isize,
+    /// Number of blocks needed to cover the whole query.
+    pub(crate) max_num_blocks: usize,
+    /// Size of padding in last block.
+    pub(crate) w: usize,
+    /// Threshold number of differences.
+    pub(crate) k: isize,
+    /// Horizontal delta at top of column. `0` if gap before query is not penalized.
+    start_hout: isize,
+    /// Length of query.
+    query_len: isize,
+    /// Length of target, if whole target has to be aligned.
+    target_len: Option<isize>,
+    /// Number of columns calculated.
+    pub(crate) num_columns: usize,
+}
+
+impl ColumnState {
+    /// Initialize column before first column of target.
+    ///
+    /// # Arguments
+    /// * `max_num_blocks`: Number of blocks needed to cover the whole query.
+    /// * `w`: Size of padding in last block.
+    /// * `query_len`: Length of query.
+    /// * `k`: Threshold number of differences.
+    /// * `mode`: [`AlignMode`]. [`AlignMode::NW`] behaves like [`AlignMode::SHW`] as target length is unknown.
+    pub(crate) fn new(
+        max_num_blocks: usize,
+        w: usize,
+        query_len: usize,
+        k: usize,
+        mode: &AlignMode,
+    ) -> anyhow::Result<Self> {
+        let word_size = usize::try_from(WORD_SIZE)?;
+
+        // For HW, solution will never be larger than query length.
+        let k = if *mode == AlignMode::HW {
+            std::cmp::min(query_len, k)
+        } else {
+            k
+        };
+        let last_block_idx = std::cmp::min(ceil_div!(k + 1, word_size), max_num_blocks) - 1;
+
+        // Initialize blocks to some score and default bit vecs.
+        let blocks: Vec<Block> = (0..max_num_blocks)
+            .map(|blk_n| Block {
+                p: Word::MAX,
+                m: 0,
+                score: ((blk_n + 1) * word_size) as isize,
+            })
+            .collect();
+
+        Ok(ColumnState {
+            blocks,
+            first_block_idx: 0,
+            last_block_idx: last_block_idx.try_into()?,
+            max_num_blocks,
+            w,
+            k: k.try_into()?,
+            // If HW, gap before query is not penalized (hout == 0).
+            start_hout: if *mode == AlignMode::HW { 0 } else { 1 },
+            query_len: query_len.try_into()?,
+            target_len: None,
+            num_columns: 0,
+        })
+    }
+
+    /// Initialize column before first column of target for global alignment ([`AlignMode::NW`]).
+    ///
+    /// # Arguments
+    /// * `max_num_blocks`: Number of blocks needed to cover the whole query.
+    /// * `w`: Size of padding in last block.
+    /// * `query_len`: Length of query.
+    /// * `target_len`: Length of target.
+    /// * `k`: Threshold number of differences. At least difference of lengths.
+    pub(crate) fn new_global(
+        max_num_blocks: usize,
+        w: usize,
+        query_len: usize,
+        target_len: usize,
+        k: usize,
+    ) -> anyhow::Result<Self> {
+        if k < target_len.abs_diff(query_len) {
+            bail!("k of {k} is smaller than difference of query and target lengths.")
+        }
+        let k = std::cmp::min(k, std::cmp::max(query_len, target_len));
+        let mut state = ColumnState::new(max_num_blocks, w, query_len, k, &AlignMode::SHW)?;
+        // Last cell is reachable only from rows within k of its diagonal.
+        let band_len = std::cmp::min(k, (k + query_len - target_len) / 2) + 1;
+        let word_size = usize::try_from(WORD_SIZE)?;
+        state.last_block_idx =
+            (std::cmp::min(max_num_blocks, ceil_div!(band_len, word_size)) - 1).try_into()?;
+        state.target_len = Some(target_len.try_into()?);
+        Ok(state)
+    }
+
+    /// Check if band stopped existing. Cells of any following column will be larger than `k`.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.last_block_idx < self.first_block_idx
+    }
+
+    /// Number of blocks within band.
+    pub(crate) fn num_band_blocks(&self) -> usize {
+        (self.last_block_idx - self.first_block_idx + 1).max(0) as usize
+    }
+
+    /// Calculate next column and adjust band.
+    ///
+    /// # Arguments
+    /// * `peq_c`: Query profile of target symbol for each block. Length of `max_num_blocks`.
+    pub(crate) fn advance(&mut self, peq_c: &[Word]) -> anyhow::Result<()> {
+        let int_word_size = isize::try_from(WORD_SIZE)?;
+        let max_last_block_idx = isize::try_from(self.max_num_blocks)? - 1;
+        let c = self.num_columns;
+        self.num_columns += 1;
+
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        // Calculate column.
+        let mut hout = self.start_hout;
+        for block_idx in self.first_block_idx..=self.last_block_idx {
+            let block_idx = block_idx as usize;
+            let block = &mut self.blocks[block_idx];
+            hout = block.calculate_hout_delta(peq_c[block_idx], hout)?;
+            block.score += hout;
+        }
+
+        // Row of current column on diagonal through last cell, if target length is known.
+        // Cells with score s are only on a path to last cell within k if at most k - s rows from it.
+        let end_diagonal_row = self
+            .target_len
+            .map(|target_len| isize::try_from(c).map(|c| c - target_len + self.query_len))
+            .transpose()?;
+        if let Some(target_len) = self.target_len {
+            // Lower k only at end of column, as it would slow calculation too much otherwise.
+            // Padding is added for last block, as its score is result of `w` cells to the left and up.
+            let padding = if self.last_block_idx == max_last_block_idx {
+                isize::try_from(self.w)?
+            } else {
+                0
+            };
+            let remaining = std::cmp::max(
+                target_len - isize::try_from(c)? - 1,
+                self.query_len - (self.last_block_idx + 1) * int_word_size,
+            );
+            self.k = std::cmp::min(
+                self.k,
+                self.blocks[self.last_block_idx as usize].score + remaining + padding,
+            );
+        }
+        let k = self.k;
+        // Block is below band by score of its bottom cell.
+        let below_band = |block_idx: isize, score: isize| {
+            end_diagonal_row.is_some_and(|row| {
+                (block_idx + 1) * int_word_size - 1 > k - score + 2 * int_word_size - 1 + row
+            })
+        };
+
+        // Adjust number of blocks according to Ukkonen.
+        let last_block_idx = self.last_block_idx as usize;
+        let last_score = self.blocks[last_block_idx].score;
+        let extend = self.last_block_idx < max_last_block_idx
+            && match end_diagonal_row {
+                // Only next block, as others are certainly below band.
+                Some(row) => {
+                    (self.last_block_idx + 1) * int_word_size - 1
+                        <= k - last_score + 2 * int_word_size - 2 + row
+                }
+                None => {
+                    last_score - hout <= k
+                        && ((peq_c[last_block_idx + 1] & WORD_1) != 0 || hout < 0)
+                }
+            };
+        if extend {
+            // If score of left block is not too big, calculate one more block.
+            self.last_block_idx += 1;
+            let last_block = &mut self.blocks[last_block_idx + 1];
+            last_block.p = Word::MAX;
+            last_block.m = 0;
+            last_block.score = last_score - hout
+                + int_word_size
+                + last_block.calculate_hout_delta(peq_c[last_block_idx + 1], hout)?;
+        }
+        if !extend || end_diagonal_row.is_some() {
+            while self.last_block_idx >= self.first_block_idx && {
+                let score = self.blocks[self.last_block_idx as usize].score;
+                score >= k + int_word_size || below_band(self.last_block_idx, score)
+            } {
+                self.last_block_idx -= 1;
+            }
+        }
+
+        // Every some columns, do some expensive but also more efficient block reducing.
+        // This is important!
+        //
+        // Reduce the band by decreasing last block if possible.
+        if c.is_multiple_of(STRONG_REDUCE_NUM) {
+            while self.last_block_idx >= self.first_block_idx
+                && match end_diagonal_row {
+                    Some(row) => !self
+                        .block_cells(self.last_block_idx as usize)
+                        .any(|(r, score)| score <= k && r <= k - score + row + 1),
+                    None => self.blocks[self.last_block_idx as usize].all_block_cells_larger(k),
+                }
+            {
+                self.last_block_idx -= 1;
+            }
+        }
+
+        // For HW, even if all cells are > k, there still may be solution in next
+        // column because starting conditions at upper boundary are 0.
+        // That means that first block is always candidate for solution,
+        // and we can never end calculation before last column.
+        if self.start_hout == 0 {
+            if self.last_block_idx == -1 {
+                self.last_block_idx += 1;
+            }
+        } else {
+            // Reduce band by increasing first block if possible. Not applicable to HW.
+            while self.first_block_idx <= self.last_block_idx && {
+                let score = self.blocks[self.first_block_idx as usize].score;
+                // Block is above band by score of its bottom cell.
+                score >= k + int_word_size
+                    || end_diagonal_row.is_some_and(|row| {
+                        (self.first_block_idx + 1) * int_word_size - 1 < score - k + row
+                    })
+            } {
+                self.first_block_idx += 1;
+            }
+            // Do strong reduction every some blocks.
+            if c.is_multiple_of(STRONG_REDUCE_NUM) {
+                while self.first_block_idx <= self.last_block_idx
+                    && match end_diagonal_row {
+                        Some(row) => !self
+                            .block_cells(self.first_block_idx as usize)
+                            .any(|(r, score)| score <= k && r >= score - k + row),
+                        None => {
+                            self.blocks[self.first_block_idx as usize].all_block_cells_larger(k)
+                        }
+                    }
+                {
+                    self.first_block_idx += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rows and scores of cells of query in block, from bottom to top.
+    fn block_cells(&self, block_idx: usize) -> impl Iterator<Item = (isize, isize)> {
+        // Skip padded wildcard cells at the end of query.
+        let num_padded = if block_idx == self.max_num_blocks - 1 {
+            self.w
+        } else {
+            0
+        };
+        let bottom_row = ((block_idx + 1) * WORD_SIZE as usize) as isize - 1;
+        self.blocks[block_idx]
+            .get_cell_values()
+            .into_iter()
+            .enumerate()
+            .skip(num_padded)
+            .map(move |(offset, score)| (bottom_row - offset as isize, score))
+    }
+
+    /// Score of last cell of query in current column.
+    ///
+    /// # Returns
+    /// * Score, if last block is within band and score is not larger than `k`.
+    ///     * Scores > k don't have correct values (so we cannot use them), but are certainly > k.
+    pub(crate) fn last_row_score(&self) -> Option<usize> {
+        if self.is_empty() || self.last_block_idx as usize != self.max_num_blocks - 1 {
+            return None;
+        }
+        // Skip padded wildcard cells at the end of query.
+        let score = self.blocks[self.max_num_blocks - 1].get_cell_value(self.w);
+        (score <= self.k).then_some(score as usize)
+    }
+
+    /// Minimum score of all cells of query in current column, including the cell before first query cell.
+    ///
+    /// # Returns
+    /// * Minimum score, if not larger than `k`. Cells outside of band are certainly > k.
+    pub(crate) fn min_score(&self) -> Option<usize> {
+        // Cell before first query cell. Gap before query is number of columns, if penalized.
+        let top_score = self.start_hout * isize::try_from(self.num_columns).ok()?;
+        if self.is_empty() {
+            return (top_score <= self.k).then_some(top_score as usize);
+        }
+        (self.first_block_idx as usize..=self.last_block_idx as usize)
+            .flat_map(|block_idx| {
+                // Skip padded wildcard cells at the end of query.
+                let num_padded = if block_idx == self.max_num_blocks - 1 {
+                    self.w
+                } else {
+                    0
+                };
+                self.blocks[block_idx]
+                    .get_cell_values()
+                    .into_iter()
+                    .skip(num_padded)
+            })
+            .chain(std::iter::once(top_score))
+            .min()
+            .filter(|score| *score <= self.k)
+            .map(|score| score as usize)
+    }
+}
@@ -1,4 +1,11 @@
-use crate::{equal::EqualityPair, mode::AlignMode, task::AlignTask};
+//! Alignment configuration.
+
+use anyhow::bail;
+
+use crate::{
+    align::WORD_SIZE, budget::Budget, equal::EqualityPair, mode::AlignMode, progress::Progress,
+    task::AlignTask,
+};
 
 #[derive(Debug, Clone, Default)]
 /// Alignment configuration.
@@ -10,7 +17,7 @@ pub struct AlignConfig {
     /// * Non-negative:
     ///     * Edit distance is not larget than `k`.
     /// * None:
-    ///     * `k` will be auto-adjusted until a score is found.
+    ///     * `k` will be auto-adjusted until a score is found, see [`AlignConfig::k_growth`].
     /// * Small:
     ///     * Improve speed of computation.
     /// * Smaller than edit distance:
@@ -19,6 +26,12 @@ pub struct AlignConfig {
     /// https://dl.acm.org/doi/abs/10.1145/316542.316550 \[1\]
     ///
     pub k: Option<usize>,
+    /// Threshold number of differences as a fraction of query length, like an error rate.
+    /// * `k` is the fraction of query length rounded down.
+    /// * If `k` is also set, the smaller threshold is used.
+    pub k_fraction: Option<f64>,
+    /// Growth of `k` until a score is found, if neither `k` nor `k_fraction` is set.
+    pub k_growth: KGrowth,
     /// Alignment method, [`AlignMode`].
     pub mode: AlignMode,
     /// Alignment task, [`AlignTask`].
@@ -26,4 +39,121 @@ pub struct AlignConfig {
     /// List of pairs of characters as an [`EqualityPair`], where each pair defines two characters as equal.
     /// * Allows extension of the lib's definition of equality.
     pub added_equalities: Vec<EqualityPair>,
+    /// Report best and second-best distinct hit as an [`Uniqueness`](crate::unique::Uniqueness).
+    /// * Only for [`AlignMode::HW`] and [`AlignMode::SHW`], and requires `k` or `k_fraction`.
+    /// * `k` is not lowered during search, so second-best hit is only found if its edit distance is not larger than `k`.
+    pub uniqueness: bool,
+    /// Limits on the work of alignment, like a cancellation flag or a deadline.
+    pub budget: Budget,
+    /// Callback reporting progress every some target columns. If `None`, nothing is reported.
+    pub progress: Option<Progress>,
+    /// Record band and cells of every calculated column in [`Alignment::trace`](crate::align::Alignment::trace).
+    /// * Memory is quadratic, so only for debugging and teaching with short sequences.
+    pub trace: bool,
+    /// Choice among co-optimal paths of [`AlignTask::Path`].
+    pub traceback: TracebackOrder,
+}
+
+/// Growth of `k` while no alignment is found.
+/// * Each retry recalculates alignment from scratch with a larger `k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KGrowth {
+    /// First `k` tried.
+    pub start: usize,
+    /// Multiplier of `k` for each retry. Must be at least `2`.
+    pub factor: usize,
+    /// Largest `k` tried. If `None`, `k` grows until an alignment is found.
+    /// * If reached, [`Alignment::exhausted_k`](crate::align::Alignment::exhausted_k) is set.
+    pub max: Option<usize>,
+}
+
+impl Default for KGrowth {
+    /// Start at word size and double without limit, like the original implementation.
+    fn default() -> Self {
+        KGrowth {
+            start: WORD_SIZE as usize,
+            factor: 2,
+            max: None,
+        }
+    }
+}
+
+/// Choice between a mismatch and an indel when both are on co-optimal paths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Prefer mismatches, like `2X` over `1I1D`.
+    #[default]
+    MismatchFirst,
+    /// Prefer insertions and deletions.
+    IndelFirst,
+}
+
+/// Side to which gaps are shifted within repeats, like `AAA` aligned to `AA`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GapShift {
+    /// Gaps as close to start of alignment as possible, like `1I2=`.
+    #[default]
+    Left,
+    /// Gaps as close to end of alignment as possible, like `2=1I`.
+    Right,
+}
+
+/// Preferences among co-optimal paths.
+/// * Applied at each step of traceback from end of alignment, so a preference may be traded for an earlier one.
+/// * See [`left_normalize`](crate::normalize::left_normalize) to fully left-align indels afterwards.
+/// * Insertions are always preferred over deletions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TracebackOrder {
+    /// Choice between mismatches and indels.
+    pub tie_break: TieBreak,
+    /// Side of gaps within repeats.
+    pub gap_shift: GapShift,
+}
+
+impl TracebackOrder {
+    /// Whether diagonal step is taken before a gap in traceback from end.
+    ///
+    /// # Arguments
+    /// * `is_equal`: If diagonal step is a match.
+    pub(crate) fn diagonal_first(&self, is_equal: bool) -> bool {
+        if is_equal {
+            // Taking matches first at end leaves gaps towards start.
+            self.gap_shift == GapShift::Left
+        } else {
+            self.tie_break == TieBreak::MismatchFirst
+        }
+    }
+}
+
+impl AlignConfig {
+    /// Fixed threshold of differences from `k` and `k_fraction`.
+    ///
+    /// # Arguments
+    /// * `query_len`: Length of query.
+    ///
+    /// # Returns
+    /// * Smaller of `k` and `k_fraction` of query length. `None` if neither is set.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::config::AlignConfig;
+    ///
+    /// let config = AlignConfig {
+    ///     k_fraction: Some(0.1),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(config.threshold(155).unwrap(), Some(15));
+    /// ```
+    pub fn threshold(&self, query_len: usize) -> anyhow::Result<Option<usize>> {
+        let Some(k_fraction) = self.k_fraction else {
+            return Ok(self.k);
+        };
+        if !k_fraction.is_finite() || k_fraction < 0.0 {
+            bail!("Invalid k fraction {k_fraction}.")
+        }
+        let k = (k_fraction * query_len as f64).floor() as usize;
+        Ok(Some(
+            self.k.map_or(k, |config_k| std::cmp::min(k, config_k)),
+        ))
+    }
 }
@@ -0,0 +1,313 @@
+//! Enumeration of all co-optimal alignment paths.
+
+use anyhow::bail;
+
+use crate::{
+    align::{transform_sequences, Alignment},
+    cigar::EditOp,
+    equal::{EqualityDefinition, EqualityPair},
+    naive::score_matrix,
+};
+
+/// Number of steps into a cell: diagonal, insertion and deletion.
+const NUM_STEPS: usize = 3;
+
+/// Lazy iterator over every distinct optimal global path of query and target.
+/// * Paths are yielded in a deterministic order. Going back from end, a diagonal step comes before an [`EditOp::Insert`],
+///   which comes before an [`EditOp::Delete`]. So first path is the one of default [`TracebackOrder`](crate::config::TracebackOrder).
+/// * Uses a full dynamic programming matrix, so it is meant for short sequences, like teaching and ambiguity analysis.
+/// * Number of paths can grow exponentially with length. Iteration stops after a cap, and [`CoOptimalPaths::num_paths`]
+///   reports how many paths there are without materializing them.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{cigar::EditOp, cooptimal::CoOptimalPaths};
+///
+/// let paths = CoOptimalPaths::new("AC", "CA", &[], 10);
+/// assert_eq!(paths.edit_distance(), 2);
+/// assert_eq!(paths.num_paths(), Some(3));
+/// let paths: Vec<Vec<EditOp>> = paths.collect();
+/// assert_eq!(paths[0], [EditOp::Mismatch, EditOp::Mismatch]);
+/// assert_eq!(paths[1], [EditOp::Delete, EditOp::Match, EditOp::Insert]);
+/// assert_eq!(paths[2], [EditOp::Insert, EditOp::Match, EditOp::Delete]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CoOptimalPaths {
+    /// Transformed query.
+    query: Vec<usize>,
+    /// Transformed target.
+    target: Vec<usize>,
+    equality_def: EqualityDefinition,
+    /// Scores by column.
+    matrix: Vec<usize>,
+    /// Cells of current partial path from end, with next step to try into each.
+    stack: Vec<(usize, usize, usize)>,
+    /// Steps into all cells of stack but end, from end.
+    steps: Vec<EditOp>,
+    /// Number of paths left to yield before cap.
+    remaining: usize,
+}
+
+impl CoOptimalPaths {
+    /// Prepare enumeration of optimal paths of whole query and target.
+    ///
+    /// # Arguments
+    /// * `query`: Aligned query.
+    /// * `target`: Aligned part of target.
+    /// * `added_equalities`: Extra [`EqualityPair`]s of query and target characters.
+    /// * `max_paths`: Cap on number of paths to yield.
+    pub fn new(
+        query: &str,
+        target: &str,
+        added_equalities: &[EqualityPair],
+        max_paths: usize,
+    ) -> Self {
+        let (alphabet, query, target) = transform_sequences(query, target);
+        let equality_def = EqualityDefinition::new(&alphabet, Some(added_equalities));
+        let matrix = score_matrix(&query, &target, &equality_def);
+        let stack = vec![(query.len(), target.len(), 0)];
+        CoOptimalPaths {
+            query,
+            target,
+            equality_def,
+            matrix,
+            stack,
+            steps: vec![],
+            remaining: max_paths,
+        }
+    }
+
+    /// Edit distance of every path.
+    pub fn edit_distance(&self) -> usize {
+        self.score(self.query.len(), self.target.len())
+    }
+
+    /// Number of optimal paths, regardless of cap and of paths already yielded.
+    ///
+    /// # Returns
+    /// * Number of paths. `None` if it does not fit in [`u128`].
+    pub fn num_paths(&self) -> Option<u128> {
+        let num_rows = self.query.len() + 1;
+        // Number of optimal paths from start to each cell, by column.
+        let mut counts: Vec<u128> = vec![0; self.matrix.len()];
+        counts[0] = 1;
+        for column in 0..=self.target.len() {
+            for row in 0..num_rows {
+                for step in 0..NUM_STEPS {
+                    if let Some((_, prev_row, prev_column)) = self.step(row, column, step) {
+                        counts[column * num_rows + row] = counts[column * num_rows + row]
+                            .checked_add(counts[prev_column * num_rows + prev_row])?;
+                    }
+                }
+            }
+        }
+        counts.last().copied()
+    }
+
+    /// Score of cell after `row` query and `column` target symbols.
+    fn score(&self, row: usize, column: usize) -> usize {
+        self.matrix[column * (self.query.len() + 1) + row]
+    }
+
+    /// Optimal step into a cell.
+    ///
+    /// # Arguments
+    /// * `row`, `column`: Cell.
+    /// * `step`: Index of step in order of enumeration.
+    ///
+    /// # Returns
+    /// * [`EditOp`] of step and cell before it. `None` if step is not optimal.
+    fn step(&self, row: usize, column: usize, step: usize) -> Option<(EditOp, usize, usize)> {
+        let current = self.score(row, column);
+        match step {
+            0 if row > 0 && column > 0 => {
+                let is_equal = self.equality_def[(self.query[row - 1], self.target[column - 1])];
+                let op = if is_equal {
+                    EditOp::Match
+                } else {
+                    EditOp::Mismatch
+                };
+                (self.score(row - 1, column - 1) + usize::from(!is_equal) == current).then_some((
+                    op,
+                    row - 1,
+                    column - 1,
+                ))
+            }
+            1 if row > 0 => (self.score(row - 1, column) + 1 == current).then_some((
+                EditOp::Insert,
+                row - 1,
+                column,
+            )),
+            2 if column > 0 => (self.score(row, column - 1) + 1 == current).then_some((
+                EditOp::Delete,
+                row,
+                column - 1,
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl Iterator for CoOptimalPaths {
+    type Item = Vec<EditOp>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // Every cell has an optimal step into it, so each partial path reaches start.
+        while let Some(top) = self.stack.last_mut() {
+            let (row, column, step) = *top;
+            if row == 0 && column == 0 {
+                let path = self.steps.iter().rev().copied().collect();
+                self.stack.pop();
+                self.steps.pop();
+                self.remaining -= 1;
+                return Some(path);
+            }
+            if step == NUM_STEPS {
+                self.stack.pop();
+                self.steps.pop();
+                continue;
+            }
+            top.2 += 1;
+            if let Some((op, prev_row, prev_column)) = self.step(row, column, step) {
+                self.steps.push(op);
+                self.stack.push((prev_row, prev_column, 0));
+            }
+        }
+        None
+    }
+}
+
+impl Alignment {
+    /// [`CoOptimalPaths`] between first pair of start and end locations.
+    ///
+    /// # Arguments
+    /// * `query`: Query of alignment.
+    /// * `target`: Whole target of alignment.
+    /// * `added_equalities`: Extra [`EqualityPair`]s of alignment.
+    /// * `max_paths`: Cap on number of paths to yield.
+    ///
+    /// # Returns
+    /// * Iterator over paths. Error if alignment has no start and end locations.
+    pub fn co_optimal_paths(
+        &self,
+        query: &str,
+        target: &str,
+        added_equalities: &[EqualityPair],
+        max_paths: usize,
+    ) -> anyhow::Result<CoOptimalPaths> {
+        let (Some(start), Some(end)) = (
+            self.start_locations.as_ref().and_then(|locs| locs.first()),
+            self.end_locations.as_ref().and_then(|locs| locs.first()),
+        ) else {
+            bail!("No start and end locations to enumerate paths between.")
+        };
+        let (start, end) = (usize::try_from(*start)?, usize::try_from(end + 1)?);
+        let aligned_target: String = target.chars().skip(start).take(end - start).collect();
+        Ok(CoOptimalPaths::new(
+            query,
+            &aligned_target,
+            added_equalities,
+            max_paths,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cigar::Cigar, config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+    fn cigars(paths: CoOptimalPaths) -> Vec<String> {
+        paths
+            .map(|path| Cigar::from_path(&path, Default::default()).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_co_optimal_paths() {
+        // Deletion of one of three As.
+        let paths = CoOptimalPaths::new("GAAT", "GAAAT", &[], 10);
+        assert_eq!(paths.num_paths(), Some(3));
+        assert_eq!(cigars(paths), ["1=1D3=", "2=1D2=", "3=1D1="]);
+
+        // Cap.
+        let paths = CoOptimalPaths::new("GAAT", "GAAAT", &[], 2);
+        assert_eq!(paths.num_paths(), Some(3));
+        assert_eq!(paths.num_paths(), Some(3));
+        assert_eq!(cigars(paths).len(), 2);
+        assert_eq!(
+            CoOptimalPaths::new("GAAT", "GAAAT", &[], 0).num_paths(),
+            Some(3)
+        );
+
+        // Empty sequences have one empty path.
+        let mut paths = CoOptimalPaths::new("", "", &[], 10);
+        assert_eq!(paths.next(), Some(vec![]));
+        assert_eq!(paths.next(), None);
+        assert_eq!(cigars(CoOptimalPaths::new("", "AC", &[], 10)), ["2D"]);
+
+        // Added equality.
+        let equalities = [EqualityPair {
+            first: 'N',
+            second: 'A',
+        }];
+        let paths = CoOptimalPaths::new("N", "A", &equalities, 10);
+        assert_eq!(paths.edit_distance(), 0);
+        assert_eq!(cigars(paths), ["1="]);
+    }
+
+    #[test]
+    fn test_co_optimal_count() {
+        for (query, target) in [("ACAC", "CACA"), ("ACGTT", "TGCA"), ("AAAA", "CCCC")] {
+            let paths = CoOptimalPaths::new(query, target, &[], usize::MAX);
+            let count = paths.num_paths().unwrap();
+            let edit_distance = paths.edit_distance();
+            let mut paths: Vec<Vec<EditOp>> = paths.collect();
+            assert_eq!(paths.len() as u128, count);
+            for path in paths.iter() {
+                let cost = path.iter().filter(|op| **op != EditOp::Match).count();
+                assert_eq!(cost, edit_distance);
+            }
+            paths.sort_by_key(|path| format!("{path:?}"));
+            paths.dedup();
+            assert_eq!(paths.len() as u128, count);
+        }
+
+        // Too many paths to count.
+        let query = "A".repeat(200);
+        let target = "A".repeat(400);
+        assert_eq!(
+            CoOptimalPaths::new(&query, &target, &[], 1).num_paths(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_co_optimal_alignment() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            task: AlignTask::Loc,
+            ..Default::default()
+        };
+        let (query, target) = ("GCAT", "TTGCAATTT");
+        let aln = Alignment::run(config, query, target).unwrap();
+        assert_eq!(aln.end_locations, Some(vec![4, 5, 6]));
+        let paths = aln.co_optimal_paths(query, target, &[], 10).unwrap();
+        assert_eq!(paths.edit_distance(), 1);
+        assert_eq!(cigars(paths), ["3=1I"]);
+
+        let aln = Alignment {
+            start_locations: Some(vec![2]),
+            end_locations: Some(vec![6]),
+            ..Default::default()
+        };
+        let paths = aln.co_optimal_paths(query, target, &[], 10).unwrap();
+        assert_eq!(cigars(paths), ["2=1D2=", "3=1D1="]);
+
+        let aln = Alignment::run(AlignConfig::default(), query, target).unwrap();
+        assert!(aln.co_optimal_paths(query, target, &[], 10).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//! Fuzzy lookup of words in a dictionary.
+
+use anyhow::bail;
+
+use crate::{
+    config::AlignConfig,
+    mode::AlignMode,
+    state::{MyersState, Snapshot},
+};
+
+/// Node of trie of dictionary entries.
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    /// Child nodes by next character, sorted by character.
+    children: Vec<(char, usize)>,
+    /// Index of entry ending at node.
+    entry: Option<usize>,
+}
+
+/// Entry of [`Dictionary`] found by [`Dictionary::lookup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictionaryMatch<'a> {
+    /// Dictionary entry.
+    pub entry: &'a str,
+    /// Edit distance ([`AlignMode::NW`]) between query and entry.
+    pub edit_distance: usize,
+}
+
+/// Dictionary of words with fuzzy lookup.
+/// * Entries may have any characters, but queries only characters supported by [`MyersState`].
+/// * Entries are stored in a trie, so the columns of shared prefixes are only calculated once.
+/// * Subtrees are pruned once all cells of a column are larger than `k`.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{config::AlignConfig, dictionary::Dictionary};
+///
+/// let dictionary = Dictionary::new(["BRCA1", "BRCA2", "BRAF", "KRAS"]);
+/// let config = AlignConfig {
+///     k: Some(1),
+///     ..Default::default()
+/// };
+/// let matches = dictionary.lookup(config, "BRCA").unwrap();
+/// let entries: Vec<&str> = matches.iter().map(|m| m.entry).collect();
+/// assert_eq!(entries, ["BRCA1", "BRCA2"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    /// Trie nodes. First node is root.
+    nodes: Vec<TrieNode>,
+    entries: Vec<String>,
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Dictionary {
+            nodes: vec![TrieNode::default()],
+            entries: vec![],
+        }
+    }
+}
+
+impl Dictionary {
+    /// Initialize a new `Dictionary` from entries.
+    ///
+    /// # Arguments
+    /// * `entries`: Words of dictionary. Duplicates are ignored.
+    pub fn new(entries: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let mut dictionary = Dictionary::default();
+        for entry in entries {
+            dictionary.insert(entry);
+        }
+        dictionary
+    }
+
+    /// Add entry to dictionary.
+    ///
+    /// # Arguments
+    /// * `entry`: Word to add.
+    ///
+    /// # Returns
+    /// * If entry was not already in dictionary.
+    pub fn insert(&mut self, entry: impl AsRef<str>) -> bool {
+        let entry = entry.as_ref();
+        let mut node_idx = 0;
+        for chr in entry.chars() {
+            let children = &self.nodes[node_idx].children;
+            node_idx = match children.binary_search_by_key(&chr, |(child_chr, _)| *child_chr) {
+                Ok(pos) => children[pos].1,
+                Err(pos) => {
+                    let child_idx = self.nodes.len();
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[node_idx].children.insert(pos, (chr, child_idx));
+                    child_idx
+                }
+            };
+        }
+        if self.nodes[node_idx].entry.is_some() {
+            return false;
+        }
+        self.nodes[node_idx].entry = Some(self.entries.len());
+        self.entries.push(entry.to_owned());
+        true
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if dictionary has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Find all entries within edit distance `k` of query.
+    ///
+    /// # Arguments
+    /// * `config`: [`AlignConfig`] configuration.
+    ///     * `k` or `k_fraction` is required.
+    ///     * `mode` must be [`AlignMode::NW`].
+    ///     * `task` is ignored.
+    /// * `query`: Word to look up.
+    ///
+    /// # Returns
+    /// * [`DictionaryMatch`]es sorted by edit distance and then entry.
+    ///   Error if query has characters beyond [`MAX_UCHAR`](crate::align::MAX_UCHAR).
+    pub fn lookup(
+        &self,
+        config: AlignConfig,
+        query: impl AsRef<str>,
+    ) -> anyhow::Result<Vec<DictionaryMatch<'_>>> {
+        if config.mode != AlignMode::NW {
+            bail!("Dictionary lookup requires AlignMode::NW.")
+        }
+        let query = query.as_ref();
+        let Some(k) = config.threshold(query.chars().count())? else {
+            bail!("Dictionary lookup requires k.")
+        };
+
+        let mut matches = vec![];
+        if query.is_empty() {
+            // Edit distance is length of entry.
+            matches.extend(
+                self.entries
+                    .iter()
+                    .map(|entry| DictionaryMatch {
+                        entry,
+                        edit_distance: entry.chars().count(),
+                    })
+                    .filter(|entry_match| entry_match.edit_distance <= k),
+            );
+        } else {
+            let mut state = MyersState::new(config, query)?;
+            self.walk(0, 0, &mut state, &mut vec![], &mut matches)?;
+        }
+        matches.sort_by(|a, b| (a.edit_distance, a.entry).cmp(&(b.edit_distance, b.entry)));
+        Ok(matches)
+    }
+
+    /// Depth-first walk of trie calculating one column per node.
+    /// * `snapshots` has one [`Snapshot`] per depth, reused by all nodes of that depth.
+    fn walk<'a>(
+        &'a self,
+        node_idx: usize,
+        depth: usize,
+        state: &mut MyersState,
+        snapshots: &mut Vec<Snapshot>,
+        matches: &mut Vec<DictionaryMatch<'a>>,
+    ) -> anyhow::Result<()> {
+        let node = &self.nodes[node_idx];
+        if let (Some(entry_idx), Some(edit_distance)) = (node.entry, state.score()) {
+            matches.push(DictionaryMatch {
+                entry: &self.entries[entry_idx],
+                edit_distance,
+            })
+        }
+        // Prune subtree if all cells are larger than k.
+        if node.children.is_empty() || state.min_score().is_none() {
+            return Ok(());
+        }
+
+        match snapshots.get_mut(depth) {
+            Some(snapshot) => state.snapshot_into(snapshot),
+            None => snapshots.push(state.snapshot()),
+        }
+        for (chr, child_idx) in node.children.iter() {
+            state.push_char(*chr)?;
+            self.walk(*child_idx, depth + 1, state, snapshots, matches)?;
+            state.restore(&snapshots[depth]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::equal::EqualityPair;
+
+    fn config(k: usize) -> AlignConfig {
+        AlignConfig {
+            k: Some(k),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dictionary_lookup() {
+        let mut dictionary = Dictionary::new(["kitten", "sitting", "mitten", "smitten", "kit"]);
+        assert!(!dictionary.insert("kitten"));
+        assert_eq!(dictionary.len(), 5);
+
+        let matches = dictionary.lookup(config(2), "sitten").unwrap();
+        assert_eq!(
+            matches,
+            [
+                DictionaryMatch {
+                    entry: "kitten",
+                    edit_distance: 1
+                },
+                DictionaryMatch {
+                    entry: "mitten",
+                    edit_distance: 1
+                },
+                // Insertion of m.
+                DictionaryMatch {
+                    entry: "smitten",
+                    edit_distance: 1
+                },
+                DictionaryMatch {
+                    entry: "sitting",
+                    edit_distance: 2
+                },
+            ]
+        );
+        assert!(dictionary.lookup(config(0), "sitten").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dictionary_lookup_k_fraction() {
+        let dictionary = Dictionary::new(["kitten", "sitting", "mitten", "smitten", "kit"]);
+        let config = AlignConfig {
+            k_fraction: Some(0.2),
+            ..Default::default()
+        };
+        // k is 1 for query of 6 characters.
+        let matches = dictionary.lookup(config, "sitten").unwrap();
+        assert_eq!(matches.len(), 3);
+        assert!(dictionary.lookup(AlignConfig::default(), "sitten").is_err());
+    }
+
+    #[test]
+    fn test_dictionary_lookup_added_equality() {
+        let dictionary = Dictionary::new(["naive", "naïve", "native"]);
+        let config = AlignConfig {
+            k: Some(1),
+            added_equalities: vec![EqualityPair {
+                first: 'ï',
+                second: 'i',
+            }],
+            ..Default::default()
+        };
+        let matches = dictionary.lookup(config, "naïve").unwrap();
+        assert_eq!(
+            matches
+                .iter()
+                .map(|found| (found.entry, found.edit_distance))
+                .collect::<Vec<_>>(),
+            [("naive", 0), ("naïve", 0), ("native", 1)]
+        );
+    }
+
+    #[test]
+    fn test_dictionary_lookup_empty_query() {
+        let dictionary = Dictionary::new(["", "a", "abc"]);
+        let matches = dictionary.lookup(config(1), "").unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].entry, "a");
+    }
+
+    #[test]
+    fn test_dictionary_lookup_unicode() {
+        let dictionary = Dictionary::new(["café", "cafe", "naïve", "日本"]);
+        let matches = dictionary.lookup(config(1), "cafe").unwrap();
+        assert_eq!(matches[0].entry, "cafe");
+        assert_eq!(matches[1].entry, "café");
+        assert_eq!(matches.len(), 2);
+        // Entry characters beyond query alphabet never match.
+        let matches = dictionary.lookup(config(2), "ab").unwrap();
+        assert_eq!(
+            matches,
+            [DictionaryMatch {
+                entry: "日本",
+                edit_distance: 2
+            }]
+        );
+        let matches = dictionary.lookup(config(2), "").unwrap();
+        assert_eq!(matches.len(), 1);
+        // Latin-1 characters are supported in queries.
+        let matches = dictionary.lookup(config(1), "naive").unwrap();
+        assert_eq!(matches[0].entry, "naïve");
+        assert!(dictionary.lookup(config(1), "日本").is_err());
+    }
+}
@@ -1,15 +1,77 @@
+//! Edit distance calculation with Myers' bit-vector algorithm.
+
 use anyhow::bail;
 
 use crate::{
-    align::{Alignment, AlignmentData, Word, WORD_1, WORD_SIZE},
-    block::Block,
-    ceil_div,
+    align::{Alignment, AlignmentData, Word},
+    budget::Meter,
+    column::ColumnState,
     mode::AlignMode,
 };
 
 /// Each column is reduced in more expensive way.
 pub const STRONG_REDUCE_NUM: usize = 2048;
 
+/// Find start location of an optimal alignment given its end location.
+/// * Query and target are aligned in reverse with [`AlignMode::SHW`] starting from end location.
+///
+/// # Arguments
+/// * `rev_peq`: Query profile of reversed query.
+/// * `w`: Size of padding in last block.
+/// * `max_num_blocks`: Number of blocks needed to cover the whole query.
+/// * `query_len`: Length of query.
+/// * `target`: Transformed target sequence.
+/// * `end_location`: Zero-based position in target where alignment ends.
+/// * `edit_distance`: Edit distance of alignment.
+/// * `meter`: Work counted against [`Budget`](crate::budget::Budget) and progress reporter.
+///
+/// # Returns
+/// * Zero-based position in target where alignment starts.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn find_start_location(
+    rev_peq: &[Word],
+    w: usize,
+    max_num_blocks: usize,
+    query_len: usize,
+    target: &[usize],
+    end_location: usize,
+    edit_distance: usize,
+    meter: &mut Meter,
+) -> anyhow::Result<isize> {
+    // Alignment cannot span more than query length plus edit distance target characters.
+    let rev_target: Vec<usize> = target[..=end_location]
+        .iter()
+        .rev()
+        .take(query_len + edit_distance)
+        .copied()
+        .collect();
+
+    let mut rev_alignment = Alignment::default();
+    meter.quiet(|meter| {
+        rev_alignment.calc_edit_dst_semi_global(
+            rev_peq,
+            w,
+            max_num_blocks,
+            query_len,
+            &rev_target,
+            edit_distance,
+            &AlignMode::SHW,
+            meter,
+        )
+    })?;
+
+    let Some(rev_last_loc) = rev_alignment
+        .end_locations
+        .as_ref()
+        .and_then(|locs| locs.last())
+    else {
+        bail!("No alignment with edit distance {edit_distance} ends at {end_location}.")
+    };
+    // Taking last location as start ensures that alignment will not start with insertions
+    // if it can start with mismatches instead.
+    Ok(isize::try_from(end_location)? - rev_last_loc)
+}
+
 impl Alignment {
     /// Uses Myers' bit-vector algorithm to find edit distance for one of semi-global alignment methods.
     /// * `Peq`: Query profile.
@@ -19,6 +81,8 @@ impl Alignment {
     /// * `target`
     /// * `k`
     /// * `mode`: EDLIB_MODE_HW or EDLIB_MODE_SHW
+    /// * `meter`: Work counted against [`Budget`](crate::budget::Budget) and progress reporter.
+    #[allow(clippy::too_many_arguments)]
     pub fn calc_edit_dst_semi_global(
         &mut self,
         peq: &[Word],
@@ -26,159 +90,42 @@ impl Alignment {
         max_num_blocks: usize,
         query_len: usize,
         target: &[usize],
-        mut k: usize,
+        k: usize,
         mode: &AlignMode,
+        meter: &mut Meter,
     ) -> anyhow::Result<()> {
         let mut best_score: Option<usize> = None;
         let mut positions: Vec<isize> = vec![];
 
-        let word_size = usize::try_from(WORD_SIZE)?;
-        let int_word_size = isize::try_from(word_size)?;
-
-        let mut first_block_idx = 0;
-        let mut last_block_idx = std::cmp::min(ceil_div!(k + 1, word_size), max_num_blocks);
-
-        // Initialize blocks to some score and default bit vecs.
-        let mut blocks: Vec<Block> = (0..=last_block_idx)
-            .map(|blk_n| Block {
-                p: Word::MAX,
-                m: 0,
-                score: ((blk_n + 1) * word_size) as isize,
-            })
-            .collect();
-
-        // If HW , gap before query is not penalized (hout == 0).
-        let mut start_hout = 1;
-        if *mode == AlignMode::HW {
-            k = std::cmp::min(query_len, k);
-            start_hout = 0;
-        }
-        let mut k = isize::try_from(k)?;
-
-        for (idx, c) in target.iter().enumerate() {
-            let col_idx = idx + *c * max_num_blocks;
-
-            // calculate column
-            let mut hout = start_hout;
-            for block_idx in first_block_idx..=last_block_idx {
-                let block = &mut blocks[block_idx];
-                let peq_c = &peq[col_idx + block_idx];
-                hout = block.calculate_hout_delta(*peq_c, hout)?;
-                block.score += hout;
-            }
-
-            // let last_block = &mut blocks[last_block_idx - 1];
-            // let peq_c = &peq[col_idx + last_block_idx - 1];
-            let next_peq_c = &peq[col_idx + last_block_idx];
+        let mut state = ColumnState::new(max_num_blocks, w, query_len, k, mode)?;
 
-            if last_block_idx < max_num_blocks - 1
-                && blocks[last_block_idx - 1].score - hout <= k
-                && ((next_peq_c & WORD_1) != 0 || hout < 0)
-            {
-                let last_block = &mut blocks[last_block_idx - 1];
-                last_block_idx += 1;
-                last_block.p = Word::MAX;
-                last_block.m = 0;
-                last_block.score = last_block.score - hout
-                    + int_word_size
-                    + last_block.calculate_hout_delta(*next_peq_c, hout)?
-            } else {
-                loop {
-                    let last_block = &blocks[last_block_idx - 1];
-                    // CHANGE: Made > instead of >=. Why is an idx negative?
-                    if !(last_block_idx > first_block_idx && last_block.score >= k + int_word_size)
-                    {
-                        break;
-                    }
-                    last_block_idx -= 1;
-                }
-            }
-
-            // Every some columns, do some expensive but also more efficient block reducing.
-            // This is important!
-            //
-            // Reduce the band by decreasing last block if possible.
-            if c % STRONG_REDUCE_NUM == 0 {
-                loop {
-                    let last_block = &blocks[last_block_idx];
-                    // CHANGE: Remove inline function.
-                    // CHANGE: Made > instead of >=. Should prevent overflow but might have downstream effects.
-                    // See https://github.com/Martinsos/edlib/blob/master/edlib/src/edlib.cpp#L624C3-L630
-                    if !(last_block_idx > first_block_idx && last_block.all_block_cells_larger(k)) {
-                        break;
-                    }
-                    last_block_idx -= 1
-                }
-            }
-            // Reduce band by increasing first block if possible. Not applicable to HW.
-            if *mode == AlignMode::SHW {
-                while first_block_idx <= last_block_idx
-                    && blocks[first_block_idx].score >= k + int_word_size
-                {
-                    first_block_idx += 1;
-                }
-
-                // Do strong reduction every some blcoks.
-                if c % STRONG_REDUCE_NUM == 0 {
-                    let first_block = &blocks[first_block_idx];
-                    while first_block_idx <= last_block_idx && first_block.all_block_cells_larger(k)
-                    {
-                        first_block_idx += 1
-                    }
-                }
+        // Column before target. Query can start before target with a score of query length.
+        let columns = std::iter::once(None).chain(target.iter().map(Some));
+        for (position, c) in (-1..).zip(columns) {
+            if let Some(c) = c {
+                state.advance(&peq[c * max_num_blocks..(c + 1) * max_num_blocks])?;
+                meter.charge(state.num_band_blocks())?;
+                meter.report(position as usize, &state, || best_score);
+                meter.record(position as usize, &state);
             }
 
             // If band stops to exist finish.
-            if last_block_idx < first_block_idx {
-                self.edit_distance = best_score;
-                if best_score.is_some() {
-                    self.end_locations = Some(positions)
-                }
-
-                return Ok(());
+            if state.is_empty() {
+                break;
             }
 
             // Update best score.
-            if last_block_idx == max_num_blocks - 1 {
-                let col_score = blocks[last_block_idx].score;
-
-                // Scores > k don't have correct values (so we cannot use them), but are certainly > k.
-                if col_score <= k {
-                    // NOTE: Score that I find in column c == score from column c - W
-                    let int_best_score = best_score.map(|score| score as isize);
-                    if best_score.is_none() || Some(col_score) <= int_best_score {
-                        if Some(col_score) != int_best_score {
-                            positions.clear();
-                            best_score = Some(col_score.try_into()?);
-                            // Change k so we will only look for equal or better scores than the best found so far.
-                            k = col_score;
-                        }
-                        positions.push(isize::try_from(*c)? - isize::try_from(w)?)
-                    }
-                }
-            }
-        }
-
-        // Obtain results for last W columns from last column.
-        if last_block_idx == max_num_blocks - 1 {
-            let block_scores = blocks[last_block_idx].get_cell_values();
-            for block_score_idx in 0..w {
-                let col_score = block_scores[block_score_idx + 1];
-                let int_best_score = best_score.map(|score| score as isize);
-                if col_score <= k
-                    && (best_score.is_none()
-                        || int_best_score.map(|score| score >= col_score) == Some(true))
-                {
-                    if Some(col_score) != int_best_score {
-                        positions.clear();
-                        k = col_score;
-                        best_score = Some(col_score.try_into()?)
-                    }
-                    positions.push(
-                        isize::try_from(target.len())? - isize::try_from(w)?
-                            + isize::try_from(block_score_idx)?,
-                    )
+            let Some(col_score) = state.last_row_score() else {
+                continue;
+            };
+            if best_score.is_none_or(|score| col_score <= score) {
+                if Some(col_score) != best_score {
+                    positions.clear();
+                    best_score = Some(col_score);
+                    // Change k so we will only look for equal or better scores than the best found so far.
+                    state.k = col_score.try_into()?;
                 }
+                positions.push(position)
             }
         }
 
@@ -205,6 +152,8 @@ impl Alignment {
     /// * `target_stop_position`:
     ///     * If set to `None`, whole calculation is performed normally, as expected. Originally sentinel value of `-1`.
     ///     * If set to `p`, calculation is performed up to position `p` in target (inclusive) and column p is returned as the only column in align data.
+    /// * `meter`: Work counted against [`Budget`](crate::budget::Budget) and progress reporter.
+    #[allow(clippy::too_many_arguments)]
     pub fn calc_edit_dst_nw(
         &mut self,
         peq: &[Word],
@@ -216,273 +165,138 @@ impl Alignment {
         position: &mut Option<usize>,
         mut align_data: Option<&mut AlignmentData>,
         target_stop_position: Option<usize>,
+        meter: &mut Meter,
     ) -> anyhow::Result<()> {
-        let best_score: &mut Option<usize> = &mut self.edit_distance;
-        let word_size = WORD_SIZE as isize;
+        self.edit_distance = None;
+        position.take();
 
         if target_stop_position.is_some() && align_data.is_some() {
             bail!("Cannot set alignment and target_stop_position at same time.");
         };
 
+        // Each length difference costs at least one indel.
         if k < target.len().abs_diff(query_len) {
-            best_score.take();
-            position.take();
             return Ok(());
         }
-
-        let mut k = std::cmp::min(k, std::cmp::max(query_len, target.len()));
-
-        // 0-based index of first block of Ukkonen band.
-        let mut first_block_idx = 0;
-
-        /*
-        Cleaned up from https://github.com/Martinsos/edlib/blob/master/edlib/src/edlib.cpp#L755C21-L755C112
-
-        min(
-            maxNumBlocks,
-            ceilDiv(
-                min(
-                    k,
-                    (k + queryLength - targetLength) / 2
-                ) + 1,
-                WORD_SIZE
-            )
-        ) - 1
-        */
-        // 0-based index of last block of Ukkonen band.
-        // The cells below the band.
-        let mut last_block_idx = std::cmp::min(
-            max_num_blocks,
-            ceil_div!(
-                std::cmp::min(k, (k + query_len - target.len() / 2) + 1),
-                word_size as usize
-            ),
-        ) - 1;
-
-        // Initialize blocks to some score and default bit vecs.
-        let mut blocks: Vec<Block> = (0..=last_block_idx)
-            .map(|blk_n| Block {
-                p: Word::MAX,
-                m: 0,
-                score: ((blk_n as isize + 1) * word_size),
-            })
-            .collect();
-
-        // if let Some(aln_data) = align_data.as_mut() {
-        //     if target_stop_position.is_none() {
-        //         // TODO: Need to resize vectors.
-        //     }
-        // } else {
-        //     // No alignment.
-        // }
-
-        // Iterate thru columns.
-        let target_len = target.len() as isize;
-        let query_len = query_len as isize;
+        let mut state = ColumnState::new_global(max_num_blocks, w, query_len, target.len(), k)?;
 
         for (idx_c, c) in target.iter().enumerate() {
-            let col_idx = *c * max_num_blocks;
-            let c_isize = *c as isize;
-
-            // Init to hout of 1. Can range from 0, 1, and -1.
-            let mut hout: isize = 1;
-            // Adapted from Myers 1999.
-            // Computes the output score of the block given:
-            // * current level-b vertical delta vector (peq_c) given symbol c, as input
-            // * horizontal delta hin (hout, init to 1)
-            // Makes the resulting vertical delta the current one
-            // Returning the horizontal output delta, hout.
-            for block_idx in first_block_idx..=last_block_idx {
-                let block = &mut blocks[block_idx];
-                let peq_c = peq[col_idx + block_idx];
-
-                hout = block.calculate_hout_delta(peq_c, hout)?;
-                block.score += hout;
-            }
-
-            let mut last_block = &mut blocks[last_block_idx];
-            // Update k. I do it only on end of column because it would slow calculation too much otherwise. (Martinsos)
-            // NOTICE: I add W when in last block because it is actually result from W cells to the left and W cells up. (Martinsos)
-            /*
-            https://github.com/Martinsos/edlib/blob/master/edlib/src/edlib.cpp#L795C20-L797C58
-            bl->score
-                + max(
-                    targetLength - c - 1,
-                    queryLength - (
-                        (1 + lastBlock) * WORD_SIZE - 1
-                    ) - 1
-                )
-                + (lastBlock == maxNumBlocks - 1 ? W : 0)
-            */
-            let cmp = last_block.score
-                + std::cmp::max(
-                    target_len - c_isize - 1,
-                    query_len - ((1 + last_block_idx as isize) * word_size - 1) - 1,
-                )
-                + if last_block_idx == max_num_blocks - 1 {
-                    w.try_into()?
-                } else {
-                    0
-                };
-            k = std::cmp::min(k, cmp.try_into()?);
-            let k_isize = k as isize;
-
-            // Adjust number of blocks accoring to Ukkonen (Martinsos)
-            // Adjust last block (Martinsos)
-            // If block is not beneath band, calculate next block. Only next because others are certainly beneath band. (Martinsos)
-            if last_block_idx + 1 < max_num_blocks
-                && (last_block_idx as isize + 1) * word_size - 1
-                    <= k_isize - last_block.score + 2 * word_size - 2 - target_len
-                        + c_isize
-                        + query_len
-            {
-                let prev_block_score = blocks[last_block_idx].score;
-                // Next block.
-                last_block_idx += 1;
-                last_block = &mut blocks[last_block_idx];
-                last_block.p = Word::MAX;
-                last_block.m = 0;
-                // Get eq for column.
-                let peq_c = peq[col_idx + last_block_idx];
-
-                let new_hout = last_block.calculate_hout_delta(peq_c, hout)?;
-                last_block.score = prev_block_score - hout + word_size + new_hout;
-
-                // // Original impl never uses? Why?
-                // hout = new_hout
-            }
-
-            // While block is out of band, move one block up. (Martinsos)
-            // NOTE: Condition used here is more loose than the one from the article, since I simplified the max() part of it. (Martinsos)
-            // I could consider adding that max part, for optimal performance. (Martinsos)
-            // TODO: This might overflow.
-            while last_block_idx >= first_block_idx && last_block.score >= k_isize + word_size
-                || (last_block_idx as isize + 1) * word_size - 1
-                    > k_isize - last_block.score + 2 * word_size - 2 - target_len
-                        + c_isize
-                        + query_len
-                        + 1
-            {
-                last_block_idx -= 1;
-                last_block = &mut blocks[last_block_idx];
-            }
-            // Adjust first block (Martinsos)
-            // While outside of band, advance block (Martinsos)
-            while first_block_idx <= last_block_idx
-                && (blocks[first_block_idx].score >= k_isize + word_size
-                    || (first_block_idx as isize + 1) * word_size - 1
-                        < (blocks[first_block_idx].score - k_isize - target_len
-                            + query_len
-                            + c_isize))
-            {
-                first_block_idx += 1;
-            }
-
-            // TODO: consider if this part is useful, it does not seem to help much
-            if idx_c % STRONG_REDUCE_NUM == 0 {
-                let word_size = word_size as usize;
-
-                // Every some columns do more expensive but more efficient reduction
-                'outer: while last_block_idx >= first_block_idx {
-                    // If all cells outside of band remove block.
-                    let scores = blocks[last_block_idx].get_cell_values();
-                    let num_cells = if last_block_idx == max_num_blocks - 1 {
-                        word_size - w
-                    } else {
-                        word_size
-                    };
-                    let mut r = last_block_idx * word_size + num_cells - 1;
-
-                    for score in scores.iter().take(word_size).skip(word_size - num_cells) {
-                        // TODO: Does not work if do not put +1! Why???
-                        if (*score <= k_isize)
-                            && (isize::try_from(r)?
-                                <= k_isize - score - target_len + c_isize + query_len + 1)
-                        {
-                            break 'outer;
-                        }
-                        r -= 1;
-                    }
-                    // TODO: This might overflow.
-                    last_block_idx -= 1;
-                }
-
-                'outer: while first_block_idx <= last_block_idx {
-                    let scores = blocks[first_block_idx].get_cell_values();
-                    let num_cells = if first_block_idx == max_num_blocks - 1 {
-                        word_size - w
-                    } else {
-                        word_size
-                    };
-                    let mut r = first_block_idx * word_size + num_cells - 1;
-
-                    for score in scores.iter().take(word_size).skip(word_size - num_cells) {
-                        // TODO: Does not work if do not put +1! Why???
-                        if (*score <= k_isize)
-                            && (isize::try_from(r)?
-                                <= k_isize - score - target_len + c_isize + query_len + 1)
-                        {
-                            break 'outer;
-                        }
-                        r -= 1;
-                    }
-                    first_block_idx += 1;
-                }
-            }
+            state.advance(&peq[c * max_num_blocks..(c + 1) * max_num_blocks])?;
+            meter.charge(state.num_band_blocks())?;
+            meter.report(idx_c, &state, || state.min_score());
+            meter.record(idx_c, &state);
 
             // If band stops to exist finish.
-            if last_block_idx < first_block_idx {
-                best_score.take();
-                position.take();
+            if state.is_empty() {
                 return Ok(());
             }
+            let band = state.first_block_idx as usize..=state.last_block_idx as usize;
 
             // Save column so it can be used for reconstruction
             if let Some(align_data) = align_data.as_mut() {
-                if idx_c < target.len() {
-                    for (b, block) in blocks[first_block_idx..=last_block_idx].iter().enumerate() {
-                        let idx = max_num_blocks + c + b;
-                        align_data.ps[idx] = Some(block.p);
-                        align_data.ms[idx] = Some(block.m);
-                        align_data.scores[idx] = Some(block.score);
-                    }
-                    align_data.first_blocks[*c] = Some(first_block_idx);
-                    align_data.last_blocks[*c] = Some(last_block_idx);
-                }
-
-                // If this is stop column, save it and finish. (M)
-                if Some(*c) == target_stop_position {
-                    for (b, block) in blocks[first_block_idx..=last_block_idx].iter().enumerate() {
-                        align_data.ps[b] = Some(block.p);
-                        align_data.ms[b] = Some(block.m);
-                        align_data.scores[b] = Some(block.score);
-                    }
-                    align_data.first_blocks[0] = Some(first_block_idx);
-                    align_data.last_blocks[0] = Some(last_block_idx);
-                    best_score.take();
-
-                    if let (Some(position), Some(stop_position)) =
-                        (position.as_mut(), target_stop_position)
-                    {
-                        *position = stop_position;
-                    }
-                    return Ok(());
+                for block_idx in band.clone() {
+                    let block = &state.blocks[block_idx];
+                    let idx = idx_c * max_num_blocks + block_idx;
+                    align_data.ps[idx] = Some(block.p);
+                    align_data.ms[idx] = Some(block.m);
+                    align_data.scores[idx] = Some(block.score);
                 }
+                align_data.first_blocks[idx_c] = Some(*band.start());
+                align_data.last_blocks[idx_c] = Some(*band.end());
             }
-        }
 
-        // If last block of last column was calculated.
-        if last_block_idx == max_num_blocks - 1 {
-            let last_best_score = &blocks[last_block_idx].get_cell_values()[w];
-            if last_best_score <= &isize::try_from(k)? {
-                let _ = best_score.insert(usize::try_from(*last_best_score)?);
-                let _ = position.insert(target.len() - 1);
+            // If this is stop column, save it and finish. (M)
+            if Some(idx_c) == target_stop_position {
+                let _ = position.insert(idx_c);
                 return Ok(());
             }
         }
 
-        best_score.take();
-        position.take();
+        if let Some(score) = state.last_row_score() {
+            self.edit_distance = Some(score);
+            let _ = position.insert(target.len() - 1);
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{align::Alignment, config::AlignConfig, simulate::Prng};
+
+    /// Edit distance with a plain dynamic programming matrix.
+    fn levenshtein(query: &[u8], target: &[u8]) -> usize {
+        let mut column: Vec<usize> = (0..=query.len()).collect();
+        for (j, t) in target.iter().enumerate() {
+            let mut diagonal = column[0];
+            column[0] = j + 1;
+            for (i, q) in query.iter().enumerate() {
+                let score = (diagonal + usize::from(q != t))
+                    .min(column[i] + 1)
+                    .min(column[i + 1] + 1);
+                diagonal = column[i + 1];
+                column[i + 1] = score;
+            }
+        }
+        column[query.len()]
+    }
+
+    /// Sequence of pseudo-random symbols of alphabet.
+    fn sequence(alphabet: &[u8], len: usize, rng: &mut Prng) -> Vec<u8> {
+        (0..len)
+            .map(|_| alphabet[rng.below(alphabet.len())])
+            .collect()
+    }
+
+    #[test]
+    fn test_nw_band() {
+        // Band used to start from (k + query_len - target_len / 2) blocks, with only those blocks allocated,
+        // and columns were indexed by target symbol. So bands growing over several blocks, or targets with
+        // symbols other than the first ones of alphabet, gave wrong distances or panicked.
+        let mut rng = Prng::new(7);
+        for (alphabet, query_len, target_len) in [
+            (&b"ACGT"[..], 200, 200),
+            (b"ACDEFGHIKLMNPQRSTVWY", 150, 160),
+            (b"ACGT", 40, 130),
+            (b"AC", 130, 70),
+        ] {
+            let query = sequence(alphabet, query_len, &mut rng);
+            let mut target = query.clone();
+            target.resize(target_len, b'A');
+            for idx in (0..target_len).step_by(37) {
+                target[idx] = alphabet[idx % alphabet.len()];
+            }
+            let expected = levenshtein(&query, &target);
+            for k in [None, Some(expected), Some(expected + 5)] {
+                let config = AlignConfig {
+                    k,
+                    ..Default::default()
+                };
+                let aln = Alignment::run(
+                    config,
+                    String::from_utf8_lossy(&query),
+                    String::from_utf8_lossy(&target),
+                )
+                .unwrap();
+                assert_eq!(
+                    aln.edit_distance,
+                    Some(expected),
+                    "{query_len} {target_len} {k:?}"
+                );
+            }
+            if expected > 0 {
+                let config = AlignConfig {
+                    k: Some(expected - 1),
+                    ..Default::default()
+                };
+                let aln = Alignment::run(
+                    config,
+                    String::from_utf8_lossy(&query),
+                    String::from_utf8_lossy(&target),
+                )
+                .unwrap();
+                assert_eq!(aln.edit_distance, None);
+            }
+        }
+    }
+}
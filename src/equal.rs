@@ -1,9 +1,9 @@
+//! Character equality definitions.
+
 use std::ops::Index;
 
 use anyhow::{bail, Context};
 
-use crate::align::MAX_UCHAR;
-
 #[derive(Debug, Clone)]
 /// Defines two given characters as equal.
 pub struct EqualityPair {
@@ -17,6 +17,8 @@ pub struct EqualityPair {
 #[derive(Debug, Clone)]
 pub struct EqualityDefinition {
     pub(crate) alphabet: String,
+    /// Number of characters of alphabet.
+    alphabet_len: usize,
     matrix: Vec<bool>,
 }
 
@@ -43,11 +45,13 @@ impl EqualityDefinition {
     ///
     /// let eq_def = EqualityDefinition::new(
     ///     "ATGC",
-    ///     &[EqualityPair  { first: 'A', second: 'T' }]
+    ///     Some(&[EqualityPair  { first: 'A', second: 'T' }])
     /// );
     /// ```
     pub fn new(alphabet: &str, added_equalities: Option<&[EqualityPair]>) -> Self {
-        let mut matrix = vec![false; MAX_UCHAR * MAX_UCHAR];
+        // Symbols are indices of characters, not bytes.
+        let alphabet_len = alphabet.chars().count();
+        let mut matrix = vec![false; alphabet_len * alphabet_len];
 
         // (1,2) = 7
         /*
@@ -55,31 +59,26 @@ impl EqualityDefinition {
          * * *
          * * *
          */
-        for x in 0..alphabet.len() {
-            for y in 0..alphabet.len() {
-                matrix[(y * alphabet.len()) + x] = x == y
+        for x in 0..alphabet_len {
+            for y in 0..alphabet_len {
+                matrix[(y * alphabet_len) + x] = x == y
             }
         }
 
         if let Some(added_equalities) = added_equalities {
             for added_equality in added_equalities.iter() {
-                let first_transformed = alphabet.find(added_equality.first);
-                let second_transformed = alphabet.find(added_equality.second);
+                let first_transformed = position(alphabet, added_equality.first);
+                let second_transformed = position(alphabet, added_equality.second);
                 if let (Some(first_transform_pos), Some(second_transform_pos)) =
                     (first_transformed, second_transformed)
                 {
-                    // SAFETY: First transformed char and second transformed character guaranteed to exist in alphabet.
-                    // Matrix constructed from alphabet.
-                    unsafe {
-                        *matrix.get_unchecked_mut(
-                            first_transform_pos + (alphabet.len() * second_transform_pos),
-                        ) = true
-                    }
+                    matrix[first_transform_pos + (alphabet_len * second_transform_pos)] = true
                 }
             }
         }
         EqualityDefinition {
             alphabet: alphabet.to_owned(),
+            alphabet_len,
             matrix,
         }
     }
@@ -97,19 +96,20 @@ impl EqualityDefinition {
     /// use rs_edlib::equal::EqualityDefinition;
     ///
     /// let eq_def = EqualityDefinition::new("ATGC", None);
-    /// assert!(eq_def.are_equal('T', 'T'));
+    /// assert!(eq_def.are_equal('T', 'T').unwrap());
     /// ```
     ///
     /// Invalid character.
     /// ```should_panic
     /// use rs_edlib::equal::EqualityDefinition;
-    /// ///
-    /// let eq_def = EqualityDefinition::new("ATGC", None)
+    ///
+    /// let eq_def = EqualityDefinition::new("ATGC", None);
     /// // Z not in alphabet.
-    /// eq_def.are_equal('Z', 'Z');
+    /// eq_def.are_equal('Z', 'Z').unwrap();
     /// ```
     pub fn are_equal(&self, a: char, b: char) -> anyhow::Result<bool> {
-        let (Some(pos_x), Some(pos_y)) = (self.alphabet.find(a), self.alphabet.find(b)) else {
+        let (Some(pos_x), Some(pos_y)) = (position(&self.alphabet, a), position(&self.alphabet, b))
+        else {
             bail!(
                 "One or more characters ({a}, {b}) do not exist in alphabet {}",
                 self.alphabet
@@ -130,18 +130,24 @@ impl EqualityDefinition {
     }
 }
 
+/// Index of character in alphabet, counted in characters.
+fn position(alphabet: &str, chr: char) -> Option<usize> {
+    alphabet.chars().position(|other| other == chr)
+}
+
 /// Index into `EqualityDefinition` matrix by (row, col).
 impl Index<(usize, usize)> for EqualityDefinition {
     type Output = bool;
 
     fn index(&self, index: (usize, usize)) -> &bool {
         self.matrix
-            .get(index.0 + self.alphabet.len() * index.1)
+            .get(index.0 + self.alphabet_len * index.1)
             .with_context(|| format!("Invalid index {index:?}."))
             .unwrap()
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -188,6 +194,25 @@ mod test {
         assert!(!eq_def[(1, 2)]);
     }
 
+    #[test]
+    fn test_equality_definition_multibyte_alphabet() {
+        let eq_def = EqualityDefinition::new(
+            "naïve",
+            Some(&[EqualityPair {
+                first: 'ï',
+                second: 'i',
+            }]),
+        );
+        // Sized by characters, not bytes.
+        assert_eq!(eq_def.matrix.len(), 25);
+        assert!(eq_def.are_equal('ï', 'ï').unwrap());
+        assert!(eq_def.are_equal('e', 'e').unwrap());
+        assert!(!eq_def.are_equal('v', 'e').unwrap());
+        // ï is at position 2, e at position 4.
+        assert!(eq_def[(4, 4)]);
+        assert!(!eq_def[(2, 4)]);
+    }
+
     #[test]
     #[should_panic]
     fn test_equality_invalid_definition_index() {
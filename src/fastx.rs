@@ -0,0 +1,361 @@
+//! Streaming FASTA and FASTQ reading and writing.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Context};
+
+/// Format of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FastxFormat {
+    /// `>` header and sequence over any number of lines.
+    Fasta,
+    /// `@` header, sequence, `+` separator and qualities, one line each.
+    Fastq,
+}
+
+/// FASTA or FASTQ record borrowing from buffers of a [`FastxReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FastxRecord<'a> {
+    /// Header up to first whitespace.
+    pub name: &'a str,
+    /// Rest of header after whitespace, if any.
+    pub description: Option<&'a str>,
+    /// Sequence without line breaks.
+    pub sequence: &'a str,
+    /// Phred+33 qualities of FASTQ.
+    pub qualities: Option<&'a str>,
+}
+
+impl FastxRecord<'_> {
+    /// Write record as FASTA.
+    ///
+    /// # Arguments
+    /// * `writer`: Writer of FASTA.
+    /// * `line_width`: Number of sequence symbols per line. `None` for one line.
+    pub fn write_fasta(
+        &self,
+        mut writer: impl Write,
+        line_width: Option<usize>,
+    ) -> anyhow::Result<()> {
+        self.write_header(&mut writer, '>')?;
+        match line_width {
+            Some(0) => bail!("Line width must be positive."),
+            Some(line_width) => {
+                let symbols: Vec<char> = self.sequence.chars().collect();
+                for line in symbols.chunks(line_width) {
+                    writeln!(writer, "{}", line.iter().collect::<String>())?;
+                }
+            }
+            None => writeln!(writer, "{}", self.sequence)?,
+        }
+        Ok(())
+    }
+
+    /// Write record as FASTQ.
+    ///
+    /// # Arguments
+    /// * `writer`: Writer of FASTQ.
+    ///
+    /// # Returns
+    /// * Error if record has no qualities or they are not as long as sequence.
+    pub fn write_fastq(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        let Some(qualities) = self.qualities else {
+            bail!("Record {} has no qualities for FASTQ.", self.name)
+        };
+        if qualities.chars().count() != self.sequence.chars().count() {
+            bail!(
+                "Record {} has {} qualities for sequence of length {}.",
+                self.name,
+                qualities.chars().count(),
+                self.sequence.chars().count()
+            )
+        }
+        self.write_header(&mut writer, '@')?;
+        writeln!(writer, "{}\n+\n{qualities}", self.sequence)?;
+        Ok(())
+    }
+
+    fn write_header(&self, mut writer: impl Write, marker: char) -> anyhow::Result<()> {
+        match self.description {
+            Some(description) => writeln!(writer, "{marker}{} {description}", self.name)?,
+            None => writeln!(writer, "{marker}{}", self.name)?,
+        }
+        Ok(())
+    }
+}
+
+/// Streaming reader of multi-line FASTA and 4-line FASTQ.
+/// * Format is detected by first header, and all records must have it.
+/// * Buffers are reused, so each record borrows from reader until next one is read.
+/// * Blank lines and carriage returns are ignored. Errors name the line they are on.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::fastx::FastxReader;
+///
+/// let fasta = ">seq1 first\nACGT\nAC\n>seq2\nTTT\n";
+/// let mut reader = FastxReader::new(fasta.as_bytes());
+/// let record = reader.next_record().unwrap().unwrap();
+/// assert_eq!((record.name, record.description), ("seq1", Some("first")));
+/// assert_eq!(record.sequence, "ACGTAC");
+/// let record = reader.next_record().unwrap().unwrap();
+/// assert_eq!(record.sequence, "TTT");
+/// assert!(reader.next_record().unwrap().is_none());
+/// ```
+#[derive(Debug)]
+pub struct FastxReader<R: BufRead> {
+    reader: R,
+    /// Format of first record.
+    format: Option<FastxFormat>,
+    /// Last line read, without line break. Header of next FASTA record if `has_next_header`.
+    line: String,
+    /// Number of lines read.
+    line_number: usize,
+    /// Header of current record.
+    header: String,
+    /// Line number of header.
+    header_line_number: usize,
+    /// If header of next FASTA record was read with sequence of previous one.
+    has_next_header: bool,
+    sequence: String,
+    qualities: String,
+}
+
+impl<R: BufRead> FastxReader<R> {
+    /// Reader of FASTA or FASTQ.
+    ///
+    /// # Arguments
+    /// * `reader`: Buffered source, like a [`std::io::BufReader`] of a file.
+    pub fn new(reader: R) -> Self {
+        FastxReader {
+            reader,
+            format: None,
+            line: String::new(),
+            line_number: 0,
+            header: String::new(),
+            header_line_number: 0,
+            has_next_header: false,
+            sequence: String::new(),
+            qualities: String::new(),
+        }
+    }
+
+    /// [`FastxFormat`] of records, once first one is read.
+    pub fn format(&self) -> Option<FastxFormat> {
+        self.format
+    }
+
+    /// Read next line into line buffer.
+    ///
+    /// # Returns
+    /// * `false` at end of input.
+    fn read_line(&mut self) -> anyhow::Result<bool> {
+        self.line.clear();
+        let num_bytes = self
+            .reader
+            .read_line(&mut self.line)
+            .with_context(|| format!("Failed to read line {}.", self.line_number + 1))?;
+        if num_bytes == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+        let len = self.line.trim_end_matches(['\r', '\n']).len();
+        self.line.truncate(len);
+        Ok(true)
+    }
+
+    /// Read next non-blank line.
+    ///
+    /// # Returns
+    /// * `false` at end of input.
+    fn read_nonblank_line(&mut self) -> anyhow::Result<bool> {
+        while self.read_line()? {
+            if !self.line.trim().is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Read next record.
+    ///
+    /// # Returns
+    /// * [`FastxRecord`]. `None` at end of input. Error with line number if input is malformed.
+    pub fn next_record(&mut self) -> anyhow::Result<Option<FastxRecord<'_>>> {
+        if self.has_next_header {
+            self.has_next_header = false;
+        } else if !self.read_nonblank_line()? {
+            return Ok(None);
+        }
+        self.header.clone_from(&self.line);
+        self.header_line_number = self.line_number;
+        let format = match self.header.chars().next() {
+            Some('>') => FastxFormat::Fasta,
+            Some('@') => FastxFormat::Fastq,
+            _ => bail!(
+                "Line {}: Expected header starting with > or @.",
+                self.header_line_number
+            ),
+        };
+        let input_format = *self.format.get_or_insert(format);
+        if input_format != format {
+            bail!(
+                "Line {}: {format:?} record in {input_format:?} input.",
+                self.header_line_number
+            )
+        }
+
+        self.sequence.clear();
+        self.qualities.clear();
+        match format {
+            FastxFormat::Fasta => {
+                while self.read_line()? {
+                    if self.line.starts_with('>') {
+                        self.has_next_header = true;
+                        break;
+                    }
+                    self.sequence.push_str(self.line.trim());
+                }
+            }
+            FastxFormat::Fastq => {
+                if !self.read_line()? {
+                    bail!("Line {}: Missing sequence of record.", self.line_number + 1)
+                }
+                self.sequence.push_str(self.line.trim());
+                if !self.read_line()? {
+                    bail!(
+                        "Line {}: Missing + separator of record.",
+                        self.line_number + 1
+                    )
+                }
+                if !self.line.starts_with('+') {
+                    bail!("Line {}: Expected + separator.", self.line_number)
+                }
+                if !self.read_line()? {
+                    bail!(
+                        "Line {}: Missing qualities of record.",
+                        self.line_number + 1
+                    )
+                }
+                self.qualities.push_str(self.line.trim());
+                if self.qualities.chars().count() != self.sequence.chars().count() {
+                    bail!(
+                        "Line {}: {} qualities for sequence of length {}.",
+                        self.line_number,
+                        self.qualities.chars().count(),
+                        self.sequence.chars().count()
+                    )
+                }
+            }
+        }
+
+        let header = self.header[1..].trim();
+        let (name, description) = match header.split_once(char::is_whitespace) {
+            Some((name, description)) => (name, Some(description.trim_start())),
+            None => (header, None),
+        };
+        Ok(Some(FastxRecord {
+            name,
+            description,
+            sequence: &self.sequence,
+            qualities: (format == FastxFormat::Fastq).then_some(self.qualities.as_str()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_fasta() {
+        let fasta = "\n>seq1\r\nAC\r\n\r\nGT\r\n>seq2  two words\n>seq3\nA";
+        let mut reader = FastxReader::new(fasta.as_bytes());
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!((record.name, record.sequence), ("seq1", "ACGT"));
+        assert_eq!(record.qualities, None);
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!(record.description, Some("two words"));
+        assert_eq!(record.sequence, "");
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!((record.name, record.sequence), ("seq3", "A"));
+        assert!(reader.next_record().unwrap().is_none());
+        assert_eq!(reader.format(), Some(FastxFormat::Fasta));
+
+        let mut reader = FastxReader::new("ACGT\n".as_bytes());
+        let err = reader.next_record().unwrap_err();
+        assert!(err.to_string().starts_with("Line 1:"), "{err}");
+    }
+
+    #[test]
+    fn test_read_fastq() {
+        let fastq = "@read1 sample=1\nACGT\n+\nIIII\n@read2\nAC\n+read2\n#I\n";
+        let mut reader = FastxReader::new(fastq.as_bytes());
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!(record.name, "read1");
+        assert_eq!(record.description, Some("sample=1"));
+        assert_eq!(record.qualities, Some("IIII"));
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!((record.sequence, record.qualities), ("AC", Some("#I")));
+        assert!(reader.next_record().unwrap().is_none());
+
+        for (fastq, line_number) in [
+            ("@read1\nACGT\n+\nIII\n", 4),
+            ("@read1\nACGT\nIIII\n", 3),
+            ("@read1\nACGT\n+\n", 4),
+            ("@read1\n", 2),
+            ("@read1\nACGT\n", 3),
+            ("@read1\nA\n+\nI\n>seq\nA\n", 5),
+        ] {
+            let mut reader = FastxReader::new(fastq.as_bytes());
+            let err = loop {
+                match reader.next_record() {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => panic!("{fastq}"),
+                    Err(err) => break err,
+                }
+            };
+            assert!(
+                err.to_string().starts_with(&format!("Line {line_number}:")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let record = FastxRecord {
+            name: "read1",
+            description: Some("desc"),
+            sequence: "ACGTACG",
+            qualities: Some("IIIIIII"),
+        };
+        let mut fasta = vec![];
+        record.write_fasta(&mut fasta, Some(3)).unwrap();
+        assert_eq!(
+            String::from_utf8(fasta.clone()).unwrap(),
+            ">read1 desc\nACG\nTAC\nG\n"
+        );
+        let mut reader = FastxReader::new(fasta.as_slice());
+        let read = reader.next_record().unwrap().unwrap();
+        assert_eq!(
+            read,
+            FastxRecord {
+                qualities: None,
+                ..record
+            }
+        );
+        assert!(record.write_fasta(&mut vec![], Some(0)).is_err());
+
+        let mut fastq = vec![];
+        record.write_fastq(&mut fastq).unwrap();
+        let mut reader = FastxReader::new(fastq.as_slice());
+        assert_eq!(reader.next_record().unwrap().unwrap(), record);
+
+        let record = FastxRecord {
+            qualities: None,
+            ..record
+        };
+        assert!(record.write_fastq(&mut vec![]).is_err());
+    }
+}
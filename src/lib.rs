@@ -4,13 +4,36 @@
 
 pub mod align;
 pub mod block;
+pub mod bounds;
+pub mod budget;
 pub mod cigar;
+pub(crate) mod column;
 pub mod config;
+pub mod cooptimal;
+pub mod dictionary;
 pub mod dst;
 pub mod equal;
+pub mod fastx;
+pub mod metric;
 pub mod mode;
+pub mod naive;
+pub mod normalize;
+pub mod occurrence;
+pub mod paf;
 pub mod peq;
+pub mod progress;
+pub mod project;
+pub mod sam;
+pub mod simulate;
+pub mod state;
+pub mod stats;
+pub mod stream;
 pub mod task;
+pub mod top;
+pub mod trace;
+pub mod track;
+pub mod unique;
+pub mod variant;
 
 #[macro_export]
 /// Ceiling division.
@@ -20,7 +43,7 @@ pub mod task;
 /// ```
 macro_rules! ceil_div {
     ($x:expr, $y:expr) => {
-        ($x + $y - 1) / $y
+        ($x).div_ceil($y)
     };
 }
 
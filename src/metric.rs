@@ -0,0 +1,323 @@
+//! Metric tree of sequences for nearest-sequence queries.
+
+use std::collections::BinaryHeap;
+
+use anyhow::bail;
+
+use crate::{align::Alignment, config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+/// Node of BK-tree.
+#[derive(Debug, Clone)]
+struct MetricNode {
+    sequence: String,
+    /// Child nodes by edit distance to this node, sorted by distance.
+    children: Vec<(usize, usize)>,
+}
+
+/// Sequence of [`MetricTree`] found by a query.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Neighbor<'a> {
+    /// Edit distance ([`AlignMode::NW`]) between query and sequence.
+    pub edit_distance: usize,
+    /// Sequence of tree.
+    pub sequence: &'a str,
+}
+
+/// BK-tree of sequences indexed by edit distance.
+/// * Children of a node are keyed by their edit distance to it. The triangle inequality prunes all subtrees that cannot be within the searched distance.
+/// * Each edit distance is calculated with `k` set to the largest distance still of interest, so distant sequences are rejected early.
+/// * Unlike [`Dictionary`](crate::dictionary::Dictionary), no columns are shared between sequences, so it suits long sequences with few common prefixes.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{config::AlignConfig, metric::MetricTree};
+///
+/// let tree = MetricTree::new(
+///     AlignConfig::default(),
+///     ["ACGTACGT", "ACGTACGA", "TTTTACGT", "GGGGGGGG"],
+/// )
+/// .unwrap();
+/// let neighbors = tree.within("ACGTACGG", 1).unwrap();
+/// assert_eq!(neighbors.len(), 2);
+///
+/// let nearest = tree.nearest("GGGGGGGA", 1).unwrap();
+/// assert_eq!(nearest[0].sequence, "GGGGGGGG");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetricTree {
+    config: AlignConfig,
+    /// Tree nodes. First node is root.
+    nodes: Vec<MetricNode>,
+}
+
+impl MetricTree {
+    /// Initialize a new `MetricTree` from sequences.
+    ///
+    /// # Arguments
+    /// * `config`: [`AlignConfig`] configuration.
+    ///     * `k` and `k_fraction` are ignored and `k` is set for each edit distance calculation.
+    ///     * `mode` must be [`AlignMode::NW`], as only it is a metric.
+    ///     * `task` is ignored.
+    ///     * `added_equalities` must be symmetric to keep the triangle inequality.
+    /// * `sequences`: Sequences of tree. Duplicates are ignored.
+    pub fn new(
+        config: AlignConfig,
+        sequences: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> anyhow::Result<Self> {
+        if config.mode != AlignMode::NW {
+            bail!("Metric tree requires AlignMode::NW.")
+        }
+        let mut tree = MetricTree {
+            config: AlignConfig {
+                k: None,
+                k_fraction: None,
+                task: AlignTask::Distance,
+                ..config
+            },
+            nodes: vec![],
+        };
+        for sequence in sequences {
+            tree.insert(sequence)?;
+        }
+        Ok(tree)
+    }
+
+    /// Add sequence to tree.
+    ///
+    /// # Arguments
+    /// * `sequence`: Sequence to add.
+    ///
+    /// # Returns
+    /// * If sequence was not already in tree.
+    pub fn insert(&mut self, sequence: impl AsRef<str>) -> anyhow::Result<bool> {
+        let sequence = sequence.as_ref();
+        let new_node = MetricNode {
+            sequence: sequence.to_owned(),
+            children: vec![],
+        };
+        if self.nodes.is_empty() {
+            self.nodes.push(new_node);
+            return Ok(true);
+        }
+
+        let mut node_idx = 0;
+        loop {
+            let node = &self.nodes[node_idx];
+            if node.sequence == sequence {
+                return Ok(false);
+            }
+            let Some(edit_distance) = self.edit_distance(&node.sequence, sequence, None)? else {
+                bail!("No edit distance between {} and {sequence}.", node.sequence)
+            };
+            match node
+                .children
+                .binary_search_by_key(&edit_distance, |(distance, _)| *distance)
+            {
+                Ok(pos) => node_idx = node.children[pos].1,
+                Err(pos) => {
+                    let child_idx = self.nodes.len();
+                    self.nodes.push(new_node);
+                    self.nodes[node_idx]
+                        .children
+                        .insert(pos, (edit_distance, child_idx));
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    /// Number of sequences.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check if tree has no sequences.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Find all sequences within edit distance `max_distance` of query.
+    ///
+    /// # Arguments
+    /// * `query`: Sequence to look up.
+    /// * `max_distance`: Maximum edit distance.
+    ///
+    /// # Returns
+    /// * [`Neighbor`]s sorted by edit distance and then sequence.
+    pub fn within(
+        &self,
+        query: impl AsRef<str>,
+        max_distance: usize,
+    ) -> anyhow::Result<Vec<Neighbor<'_>>> {
+        let query = query.as_ref();
+        let mut neighbors = vec![];
+        let mut stack = if self.is_empty() { vec![] } else { vec![0] };
+
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            // Children further than this cannot be within distance of query.
+            let max_child_distance = node.children.last().map_or(0, |(distance, _)| *distance);
+            let Some(edit_distance) = self.edit_distance(
+                query,
+                &node.sequence,
+                Some(max_child_distance + max_distance),
+            )?
+            else {
+                continue;
+            };
+            if edit_distance <= max_distance {
+                neighbors.push(Neighbor {
+                    edit_distance,
+                    sequence: &node.sequence,
+                })
+            }
+            // Triangle inequality.
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(distance, _)| distance.abs_diff(edit_distance) <= max_distance)
+                    .map(|(_, child_idx)| *child_idx),
+            );
+        }
+        neighbors.sort();
+        Ok(neighbors)
+    }
+
+    /// Find the `n` sequences nearest to query.
+    /// * The searched distance shrinks to the `n`-th best edit distance found so far.
+    ///
+    /// # Arguments
+    /// * `query`: Sequence to look up.
+    /// * `n`: Number of sequences.
+    ///
+    /// # Returns
+    /// * Up to `n` [`Neighbor`]s sorted by edit distance and then sequence.
+    ///     * Ties at the `n`-th edit distance are broken arbitrarily.
+    pub fn nearest(&self, query: impl AsRef<str>, n: usize) -> anyhow::Result<Vec<Neighbor<'_>>> {
+        let query = query.as_ref();
+        // Max-heap of best neighbors so far.
+        let mut best: BinaryHeap<Neighbor> = BinaryHeap::with_capacity(n + 1);
+        let mut stack = if self.is_empty() || n == 0 {
+            vec![]
+        } else {
+            vec![0]
+        };
+
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            let max_distance = worst_distance(&best, n);
+            let max_child_distance = node.children.last().map_or(0, |(distance, _)| *distance);
+            let Some(edit_distance) = self.edit_distance(
+                query,
+                &node.sequence,
+                max_distance.map(|max_distance| max_child_distance + max_distance),
+            )?
+            else {
+                continue;
+            };
+            if max_distance.is_none_or(|max_distance| edit_distance < max_distance) {
+                best.push(Neighbor {
+                    edit_distance,
+                    sequence: &node.sequence,
+                });
+                if best.len() > n {
+                    best.pop();
+                }
+            }
+
+            let max_distance = worst_distance(&best, n);
+            let mut children: Vec<(usize, usize)> = node
+                .children
+                .iter()
+                .filter(|(distance, _)| {
+                    max_distance
+                        .is_none_or(|max_distance| distance.abs_diff(edit_distance) < max_distance)
+                })
+                .map(|(distance, child_idx)| (distance.abs_diff(edit_distance), *child_idx))
+                .collect();
+            // Visit children most likely to be near first, so that the searched distance shrinks early.
+            children.sort_unstable_by(|a, b| b.cmp(a));
+            stack.extend(children.into_iter().map(|(_, child_idx)| child_idx));
+        }
+        Ok(best.into_sorted_vec())
+    }
+
+    /// Edit distance between sequences, if not larger than `k`.
+    fn edit_distance(
+        &self,
+        query: &str,
+        target: &str,
+        k: Option<usize>,
+    ) -> anyhow::Result<Option<usize>> {
+        let config = AlignConfig {
+            k,
+            ..self.config.clone()
+        };
+        let edit_distance = Alignment::run(config, query, target)?.edit_distance;
+        // Distance of empty sequences is not limited by k.
+        Ok(edit_distance.filter(|edit_distance| k.is_none_or(|k| *edit_distance <= k)))
+    }
+}
+
+/// Edit distance of `n`-th best neighbor. Unbounded until `n` neighbors are found.
+fn worst_distance(best: &BinaryHeap<Neighbor>, n: usize) -> Option<usize> {
+    if best.len() < n {
+        return None;
+    }
+    best.peek().map(|worst| worst.edit_distance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEQUENCES: [&str; 6] = ["ACGT", "ACGA", "AGGA", "TTTT", "", "ACGTACGT"];
+
+    #[test]
+    fn test_metric_tree_within() {
+        let mut tree = MetricTree::new(AlignConfig::default(), SEQUENCES).unwrap();
+        assert!(!tree.insert("ACGA").unwrap());
+        assert_eq!(tree.len(), 6);
+
+        let neighbors = tree.within("ACGG", 2).unwrap();
+        assert_eq!(
+            neighbors,
+            [
+                Neighbor {
+                    edit_distance: 1,
+                    sequence: "ACGA"
+                },
+                Neighbor {
+                    edit_distance: 1,
+                    sequence: "ACGT"
+                },
+                Neighbor {
+                    edit_distance: 2,
+                    sequence: "AGGA"
+                },
+            ]
+        );
+        assert_eq!(tree.within("", 0).unwrap()[0].sequence, "");
+    }
+
+    #[test]
+    fn test_metric_tree_nearest() {
+        let tree = MetricTree::new(AlignConfig::default(), SEQUENCES).unwrap();
+        let nearest = tree.nearest("ACGTACG", 2).unwrap();
+        assert_eq!(
+            nearest,
+            [
+                Neighbor {
+                    edit_distance: 1,
+                    sequence: "ACGTACGT"
+                },
+                Neighbor {
+                    edit_distance: 3,
+                    sequence: "ACGT"
+                },
+            ]
+        );
+        assert_eq!(tree.nearest("ACGT", 10).unwrap().len(), 6);
+        assert!(tree.nearest("ACGT", 0).unwrap().is_empty());
+    }
+}
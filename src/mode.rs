@@ -1,3 +1,5 @@
+//! Alignment modes.
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 /// How to treat gaps before and after query?
 pub enum AlignMode {
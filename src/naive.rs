@@ -0,0 +1,349 @@
+//! Plain dynamic programming aligner as a reference for [`Alignment::run`].
+
+use anyhow::bail;
+
+use crate::{
+    align::{transform_sequences, Alignment},
+    cigar::EditOp,
+    config::{AlignConfig, TracebackOrder},
+    equal::EqualityDefinition,
+    mode::AlignMode,
+    task::AlignTask,
+};
+
+impl Alignment {
+    /// Aligns two sequences with a plain O(nm) dynamic programming matrix, returning an [`Alignment`].
+    /// * Same modes, tasks and equality semantics as [`Alignment::run`], so it can be used as an oracle for it.
+    /// * No band or bit-vectors, so setup is cheap for small inputs. Memory is only linear unless a path is requested.
+    /// * Start location of each end location is the smallest one of an optimal alignment.
+    /// * Path of first pair of locations is traced back from end, choosing among co-optimal paths like [`Alignment::run`].
+    /// * `budget`, `progress`, `trace` and `uniqueness` of config are ignored.
+    ///
+    /// * @param `config`: [`AlignConfig`] configuration.
+    /// * @param `query`: First sequence.
+    /// * @param `target`: Second sequence.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, config::AlignConfig, mode::AlignMode, task::AlignTask};
+    ///
+    /// let config = AlignConfig {
+    ///     mode: AlignMode::HW,
+    ///     task: AlignTask::Loc,
+    ///     ..Default::default()
+    /// };
+    /// let aln = Alignment::run_naive(config.clone(), "ACGT", "TTACCTTACGT").unwrap();
+    /// assert_eq!(aln.edit_distance, Some(0));
+    /// assert_eq!(aln.start_locations, Some(vec![7]));
+    ///
+    /// let fast_aln = Alignment::run(config, "ACGT", "TTACCTTACGT").unwrap();
+    /// assert_eq!(fast_aln.end_locations, aln.end_locations);
+    /// ```
+    pub fn run_naive(
+        config: AlignConfig,
+        query: impl AsRef<str>,
+        target: impl AsRef<str>,
+    ) -> anyhow::Result<Self> {
+        let mut alignment = Alignment::default();
+        let (alphabet, query, target) = transform_sequences(query.as_ref(), target.as_ref());
+        alignment.alphabet_length = alphabet.chars().count();
+        let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
+
+        // Score and smallest start location of alignments ending at last row of each column.
+        // First column is before target.
+        let mut last_row: Vec<(usize, usize)> = Vec::with_capacity(target.len() + 1);
+        // Column before target. Gap before query is never free.
+        let mut column: Vec<(usize, usize)> = (0..=query.len()).map(|i| (i, 0)).collect();
+        last_row.push(column[query.len()]);
+
+        for (j, t) in target.iter().enumerate() {
+            let mut next_column = Vec::with_capacity(query.len() + 1);
+            next_column.push(match config.mode {
+                AlignMode::HW => (0, j + 1),
+                AlignMode::NW | AlignMode::SHW => (j + 1, 0),
+            });
+            for (i, q) in query.iter().enumerate() {
+                let (diagonal_score, diagonal_start) = column[i];
+                let diagonal = (
+                    diagonal_score + usize::from(!equality_def[(*q, *t)]),
+                    diagonal_start,
+                );
+                let (up_score, up_start) = next_column[i];
+                let (left_score, left_start) = column[i + 1];
+                let cell = [
+                    diagonal,
+                    (up_score + 1, up_start),
+                    (left_score + 1, left_start),
+                ]
+                .into_iter()
+                .min()
+                .expect("No cells.");
+                next_column.push(cell);
+            }
+            last_row.push(next_column[query.len()]);
+            column = next_column;
+        }
+
+        // Columns where alignment ends.
+        let end_columns: Vec<usize> = match config.mode {
+            AlignMode::NW => vec![target.len()],
+            // Gap after query is free. Empty query is only reported before target.
+            AlignMode::SHW | AlignMode::HW if query.is_empty() => vec![0],
+            AlignMode::SHW | AlignMode::HW => {
+                let best_score = last_row.iter().map(|(score, _)| *score).min();
+                (0..last_row.len())
+                    .filter(|j| Some(last_row[*j].0) == best_score)
+                    .collect()
+            }
+        };
+        let edit_distance = last_row[end_columns[0]].0;
+        // Growth of k stops at its maximum, like in Alignment::run.
+        let max_k = config.threshold(query.len())?.or(config.k_growth.max);
+        if max_k.is_some_and(|k| edit_distance > k) {
+            alignment.exhausted_k = max_k;
+            return Ok(alignment);
+        }
+
+        alignment.edit_distance = Some(edit_distance);
+        alignment.end_locations = Some(
+            end_columns
+                .iter()
+                .map(|j| isize::try_from(*j).map(|j| j - 1))
+                .collect::<Result<_, _>>()?,
+        );
+        if matches!(config.task, AlignTask::Loc | AlignTask::Path) {
+            alignment.start_locations = Some(
+                end_columns
+                    .iter()
+                    // Query ending before target starts at target.
+                    .map(|j| isize::try_from(std::cmp::min(last_row[*j].1, j.saturating_sub(1))))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+        if config.task == AlignTask::Path {
+            let start =
+                usize::try_from(alignment.start_locations.as_ref().expect("No starts.")[0])?;
+            let end = end_columns[0];
+            let path = naive_path(
+                &query,
+                &target[start.min(end)..end],
+                &equality_def,
+                &config.traceback,
+            )?;
+            alignment.alignment = Some(path);
+        }
+        Ok(alignment)
+    }
+}
+
+/// Full dynamic programming matrix of global alignment of query and target.
+///
+/// # Arguments
+/// * `query`: Transformed query.
+/// * `target`: Transformed target.
+/// * `equality_def`: Equality of query and target symbols.
+///
+/// # Returns
+/// * Scores by column, with `query.len() + 1` rows and `target.len() + 1` columns.
+pub(crate) fn score_matrix(
+    query: &[usize],
+    target: &[usize],
+    equality_def: &EqualityDefinition,
+) -> Vec<usize> {
+    let num_rows = query.len() + 1;
+    let mut matrix: Vec<usize> = Vec::with_capacity(num_rows * (target.len() + 1));
+    matrix.extend(0..num_rows);
+    for (j, t) in target.iter().enumerate() {
+        let prev = j * num_rows;
+        matrix.push(j + 1);
+        for (i, q) in query.iter().enumerate() {
+            let diagonal = matrix[prev + i] + usize::from(!equality_def[(*q, *t)]);
+            let up = matrix[prev + num_rows + i] + 1;
+            let left = matrix[prev + i + 1] + 1;
+            matrix.push(diagonal.min(up).min(left));
+        }
+    }
+    matrix
+}
+
+/// Optimal global path of query and target with a full dynamic programming matrix.
+///
+/// # Arguments
+/// * `query`: Transformed query.
+/// * `target`: Transformed target.
+/// * `equality_def`: Equality of query and target symbols.
+/// * `order`: [`TracebackOrder`] among co-optimal paths.
+///
+/// # Returns
+/// * [`EditOp`]s from start to end of query.
+fn naive_path(
+    query: &[usize],
+    target: &[usize],
+    equality_def: &EqualityDefinition,
+    order: &TracebackOrder,
+) -> anyhow::Result<Vec<EditOp>> {
+    let num_rows = query.len() + 1;
+    let matrix = score_matrix(query, target, equality_def);
+    let score = |row: usize, column: usize| matrix[column * num_rows + row];
+
+    let mut path = Vec::with_capacity(query.len() + target.len());
+    let (mut row, mut column) = (query.len(), target.len());
+    while row > 0 || column > 0 {
+        let current = score(row, column);
+        // Optimal steps back from current cell.
+        let diagonal = (row > 0 && column > 0)
+            .then(|| equality_def[(query[row - 1], target[column - 1])])
+            .filter(|is_equal| score(row - 1, column - 1) + usize::from(!is_equal) == current);
+        let insert = row > 0 && score(row - 1, column) + 1 == current;
+        let delete = column > 0 && score(row, column - 1) + 1 == current;
+
+        match diagonal {
+            Some(is_equal) if !(insert || delete) || order.diagonal_first(is_equal) => {
+                path.push(if is_equal {
+                    EditOp::Match
+                } else {
+                    EditOp::Mismatch
+                });
+                row -= 1;
+                column -= 1;
+            }
+            _ if insert => {
+                path.push(EditOp::Insert);
+                row -= 1;
+            }
+            _ if delete => {
+                path.push(EditOp::Delete);
+                column -= 1;
+            }
+            _ => bail!("No optimal step at row {row} and column {column}."),
+        }
+    }
+    path.reverse();
+    Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{GapShift, TieBreak},
+        equal::EqualityPair,
+    };
+
+    fn config(mode: AlignMode, task: AlignTask) -> AlignConfig {
+        AlignConfig {
+            mode,
+            task,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_naive_nw_path() {
+        let aln =
+            Alignment::run_naive(config(AlignMode::NW, AlignTask::Path), "ACGT", "AGTT").unwrap();
+        assert_eq!(aln.edit_distance, Some(2));
+        assert_eq!(aln.end_locations, Some(vec![3]));
+        assert_eq!(aln.start_locations, Some(vec![0]));
+        assert_eq!(
+            aln.alignment,
+            Some(vec![
+                EditOp::Match,
+                EditOp::Mismatch,
+                EditOp::Mismatch,
+                EditOp::Match
+            ])
+        );
+    }
+
+    #[test]
+    fn test_naive_hw_locations() {
+        let aln =
+            Alignment::run_naive(config(AlignMode::HW, AlignTask::Path), "ACGT", "ACTTTACGGT")
+                .unwrap();
+        assert_eq!(aln.edit_distance, Some(1));
+        assert_eq!(aln.end_locations, Some(vec![2, 3, 7, 8, 9]));
+        assert_eq!(aln.start_locations, Some(vec![0, 0, 5, 5, 5]));
+        assert_eq!(
+            aln.alignment,
+            Some(vec![
+                EditOp::Match,
+                EditOp::Match,
+                EditOp::Insert,
+                EditOp::Match
+            ])
+        );
+    }
+
+    #[test]
+    fn test_naive_empty_and_k() {
+        let aln = Alignment::run_naive(config(AlignMode::HW, AlignTask::Loc), "", "ACGT").unwrap();
+        assert_eq!(aln.edit_distance, Some(0));
+        assert_eq!(aln.end_locations, Some(vec![-1]));
+
+        let aln = Alignment::run_naive(config(AlignMode::SHW, AlignTask::Path), "AC", "").unwrap();
+        assert_eq!(aln.end_locations, Some(vec![-1]));
+        assert_eq!(aln.alignment, Some(vec![EditOp::Insert, EditOp::Insert]));
+
+        let config = AlignConfig {
+            k: Some(1),
+            ..Default::default()
+        };
+        let aln = Alignment::run_naive(config, "ACGT", "TTTT").unwrap();
+        assert_eq!(aln.edit_distance, None);
+        assert_eq!(aln.exhausted_k, Some(1));
+    }
+
+    #[test]
+    fn test_naive_added_equalities() {
+        let config = AlignConfig {
+            added_equalities: vec![EqualityPair {
+                first: 'A',
+                second: 'N',
+            }],
+            ..Default::default()
+        };
+        let aln = Alignment::run_naive(config.clone(), "ACGT", "NCGT").unwrap();
+        assert_eq!(aln.edit_distance, Some(0));
+        assert_eq!(
+            aln.edit_distance,
+            Alignment::run(config, "ACGT", "NCGT")
+                .unwrap()
+                .edit_distance
+        );
+    }
+
+    #[test]
+    fn test_naive_traceback_order() {
+        for tie_break in [TieBreak::MismatchFirst, TieBreak::IndelFirst] {
+            for gap_shift in [GapShift::Left, GapShift::Right] {
+                let config = AlignConfig {
+                    task: AlignTask::Path,
+                    traceback: TracebackOrder {
+                        tie_break,
+                        gap_shift,
+                    },
+                    ..Default::default()
+                };
+                for (query, target) in [("AAAC", "AAC"), ("ACGT", "CGTA"), ("TACA", "TCAG")] {
+                    let aln = Alignment::run_naive(config.clone(), query, target).unwrap();
+                    let fast_aln = Alignment::run(config.clone(), query, target).unwrap();
+                    assert_eq!(aln.alignment, fast_aln.alignment, "{query} {target}");
+                }
+            }
+        }
+
+        let config = |gap_shift| AlignConfig {
+            task: AlignTask::Path,
+            traceback: TracebackOrder {
+                gap_shift,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let aln = Alignment::run_naive(config(GapShift::Left), "AAAC", "AAC").unwrap();
+        assert_eq!(aln.alignment.unwrap()[0], EditOp::Insert);
+        let aln = Alignment::run_naive(config(GapShift::Right), "AAAC", "AAC").unwrap();
+        assert_eq!(aln.alignment.unwrap()[2], EditOp::Insert);
+    }
+}
@@ -0,0 +1,212 @@
+//! Left-normalization of indels in alignment paths.
+
+use anyhow::bail;
+
+use crate::{align::Alignment, cigar::EditOp};
+
+/// Shift every insertion and deletion of a path as far left as possible without changing its cost.
+/// * A gap moves left past a match if the symbol of the match equals the last symbol of the gap.
+///   So indels within homopolymers and tandem repeats end up at the start of the repeat, as variant callers expect.
+/// * Symbols are compared exactly, so matches of added equalities are never moved.
+///
+/// # Arguments
+/// * `query`: Aligned query.
+/// * `target`: Aligned part of target.
+/// * `path`: [`EditOp`]s from start to end of alignment.
+///
+/// # Returns
+/// * Normalized path. Error if path does not consume both sequences exactly.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{cigar::EditOp, normalize::left_normalize};
+///
+/// // Deletion of CA within tandem repeat CACACA.
+/// let path = [
+///     EditOp::Match,
+///     EditOp::Match,
+///     EditOp::Match,
+///     EditOp::Match,
+///     EditOp::Delete,
+///     EditOp::Delete,
+///     EditOp::Match,
+/// ];
+/// let normalized = left_normalize("CACAG", "CACACAG", &path).unwrap();
+/// assert_eq!(
+///     normalized,
+///     [
+///         EditOp::Delete,
+///         EditOp::Delete,
+///         EditOp::Match,
+///         EditOp::Match,
+///         EditOp::Match,
+///         EditOp::Match,
+///         EditOp::Match
+///     ]
+/// );
+/// ```
+pub fn left_normalize(query: &str, target: &str, path: &[EditOp]) -> anyhow::Result<Vec<EditOp>> {
+    let query: Vec<char> = query.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    let query_len = path.iter().filter(|op| **op != EditOp::Delete).count();
+    let target_len = path.iter().filter(|op| **op != EditOp::Insert).count();
+    if (query_len, target_len) != (query.len(), target.len()) {
+        bail!(
+            "Path spans {query_len} query and {target_len} target symbols, not {} and {}.",
+            query.len(),
+            target.len()
+        )
+    }
+
+    let mut path = path.to_vec();
+    // Shifted gaps can merge with gaps before them and shift further.
+    while shift_gaps_left(&query, &target, &mut path) {}
+    Ok(path)
+}
+
+/// Shift each gap left once as far as possible.
+///
+/// # Returns
+/// * If any gap was shifted.
+fn shift_gaps_left(query: &[char], target: &[char], path: &mut [EditOp]) -> bool {
+    let mut shifted = false;
+    // Positions in query and target before op.
+    let (mut query_pos, mut target_pos) = (0, 0);
+    let mut idx = 0;
+    while idx < path.len() {
+        let op = path[idx];
+        if !matches!(op, EditOp::Insert | EditOp::Delete) {
+            idx += 1;
+            query_pos += 1;
+            target_pos += 1;
+            continue;
+        }
+        let gap_len = path[idx..]
+            .iter()
+            .take_while(|gap_op| **gap_op == op)
+            .count();
+
+        // Rotate gap left past preceding matches of its last symbol.
+        let (mut start, mut query_start, mut target_start) = (idx, query_pos, target_pos);
+        while start > 0 && path[start - 1] == EditOp::Match {
+            // Preceding match is of query_start - 1 and target_start - 1.
+            let (last_gap_symbol, match_symbol) = match op {
+                EditOp::Insert => (query[query_start + gap_len - 1], target[target_start - 1]),
+                _ => (target[target_start + gap_len - 1], query[query_start - 1]),
+            };
+            if last_gap_symbol != match_symbol {
+                break;
+            }
+            path[start - 1] = op;
+            path[start + gap_len - 1] = EditOp::Match;
+            shifted = true;
+            start -= 1;
+            query_start -= 1;
+            target_start -= 1;
+        }
+
+        // Ops before end of gap consume the same symbols as before.
+        idx += gap_len;
+        match op {
+            EditOp::Insert => query_pos += gap_len,
+            _ => target_pos += gap_len,
+        }
+    }
+    shifted
+}
+
+impl Alignment {
+    /// Left-normalize indels of alignment path. See [`left_normalize`].
+    ///
+    /// # Arguments
+    /// * `query`: Query of alignment.
+    /// * `target`: Whole target of alignment.
+    pub fn left_normalize(&mut self, query: &str, target: &str) -> anyhow::Result<()> {
+        let (Some(path), Some(start), Some(end)) = (
+            self.alignment.as_ref(),
+            self.start_locations.as_ref().and_then(|locs| locs.first()),
+            self.end_locations.as_ref().and_then(|locs| locs.first()),
+        ) else {
+            bail!("No path to normalize.")
+        };
+        let (start, end) = (usize::try_from(*start)?, usize::try_from(end + 1)?);
+        let aligned_target: String = target.chars().skip(start).take(end - start).collect();
+        self.alignment = Some(left_normalize(query, &aligned_target, path)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cigar::Cigar,
+        config::{AlignConfig, GapShift, TieBreak, TracebackOrder},
+        task::AlignTask,
+    };
+
+    fn cigar(query: &str, target: &str, traceback: TracebackOrder) -> String {
+        let config = AlignConfig {
+            task: AlignTask::Path,
+            traceback,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, query, target).unwrap();
+        aln.cigar(Default::default()).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_traceback_order() {
+        let right = TracebackOrder {
+            gap_shift: GapShift::Right,
+            ..Default::default()
+        };
+        assert_eq!(cigar("GAAAT", "GAAT", Default::default()), "1=1I3=");
+        assert_eq!(cigar("GAAAT", "GAAT", right), "3=1I1=");
+
+        let indel_first = TracebackOrder {
+            tie_break: TieBreak::IndelFirst,
+            ..Default::default()
+        };
+        assert_eq!(cigar("AC", "CA", Default::default()), "2X");
+        assert_eq!(cigar("AC", "CA", indel_first), "1D1=1I");
+    }
+
+    #[test]
+    fn test_left_normalize() {
+        // Insertion at end of homopolymer.
+        let path = Cigar::from_path(
+            &left_normalize(
+                "TAAAAG",
+                "TAAAG",
+                &"4=1I1=".parse::<Cigar>().unwrap().to_path().unwrap(),
+            )
+            .unwrap(),
+            Default::default(),
+        );
+        assert_eq!(path.to_string(), "1=1I4=");
+
+        // Mismatch stops shifting.
+        let path = "1=1X1=1D".parse::<Cigar>().unwrap().to_path().unwrap();
+        let normalized = left_normalize("ACA", "AGAA", &path).unwrap();
+        assert_eq!(
+            Cigar::from_path(&normalized, Default::default()).to_string(),
+            "1=1X1D1="
+        );
+        // Lengths don't add up.
+        assert!(left_normalize("ACA", "AGA", &path).is_err());
+
+        let config = AlignConfig {
+            task: AlignTask::Path,
+            traceback: TracebackOrder {
+                gap_shift: GapShift::Right,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (query, target) = ("GCACAT", "GCACACAT");
+        let mut aln = Alignment::run(config, query, target).unwrap();
+        aln.left_normalize(query, target).unwrap();
+        assert_eq!(aln.cigar(Default::default()).unwrap().to_string(), "1=2D5=");
+    }
+}
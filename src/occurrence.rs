@@ -0,0 +1,242 @@
+//! Search for all approximate occurrences of query within target.
+
+use anyhow::bail;
+
+use crate::{
+    align::{transform_sequences, Alignment},
+    budget::Meter,
+    config::AlignConfig,
+    dst::find_start_location,
+    equal::EqualityDefinition,
+    mode::AlignMode,
+    peq::QueryProfile,
+    track::ScoreTrack,
+};
+
+/// Approximate occurrence of query within target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    /// Zero-based position in target where occurrence starts.
+    pub start_location: usize,
+    /// Zero-based position in target where occurrence ends.
+    pub end_location: usize,
+    /// Edit distance between query and occurrence.
+    pub edit_distance: usize,
+}
+
+/// Lazy iterator over all [`Occurrence`]s of query within target with edit distance not larger than `k`.
+/// * Unlike [`Alignment::run`], `k` is never lowered to the best score found so far.
+/// * Runs of adjacent end locations within `k` are collapsed into a single occurrence at the local minimum.
+///     * On ties, the leftmost end location is taken.
+pub struct Occurrences {
+    track: ScoreTrack,
+    rev_profile: QueryProfile,
+    /// Best end location and score of current run of end locations.
+    run: Option<(usize, usize)>,
+}
+
+impl Alignment {
+    /// Finds all approximate occurrences of query within target, returning an [`Occurrences`] iterator.
+    ///
+    /// * @param `config`: [`AlignConfig`] configuration.
+    ///     * `k` or `k_fraction` is required.
+    ///     * `mode` must be [`AlignMode::HW`] or [`AlignMode::SHW`].
+    ///     * `task` is ignored.
+    /// * @param `query`: First sequence.
+    /// * @param `target`: Second sequence.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, config::AlignConfig, mode::AlignMode};
+    ///
+    /// let config = AlignConfig {
+    ///     k: Some(1),
+    ///     mode: AlignMode::HW,
+    ///     ..Default::default()
+    /// };
+    /// let occurrences = Alignment::occurrences(config, "ACGT", "TTACGTTTACTTT")
+    ///     .unwrap()
+    ///     .collect::<anyhow::Result<Vec<_>>>()
+    ///     .unwrap();
+    /// assert_eq!(occurrences.len(), 2);
+    /// assert_eq!(occurrences[0].edit_distance, 0);
+    /// assert_eq!(occurrences[1].edit_distance, 1);
+    /// ```
+    pub fn occurrences(
+        config: AlignConfig,
+        query: impl AsRef<str>,
+        target: impl AsRef<str>,
+    ) -> anyhow::Result<Occurrences> {
+        if config.mode == AlignMode::NW {
+            bail!("Occurrence search requires AlignMode::HW or AlignMode::SHW.")
+        }
+        let (alphabet, transformed_query, transformed_target) =
+            transform_sequences(query.as_ref(), target.as_ref());
+        if transformed_query.is_empty() {
+            bail!("Query is empty.")
+        }
+        let Some(k) = config.threshold(transformed_query.len())? else {
+            bail!("Occurrence search requires k.")
+        };
+
+        let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
+        let alphabet_len = alphabet.chars().count();
+        let profile = QueryProfile::new(alphabet_len, &transformed_query, &equality_def)?;
+        let rev_transformed_query: Vec<usize> = transformed_query.iter().rev().copied().collect();
+        let rev_profile = QueryProfile::new(alphabet_len, &rev_transformed_query, &equality_def)?;
+
+        Ok(Occurrences {
+            track: ScoreTrack::new(profile, transformed_target, k, &config.mode)?,
+            rev_profile,
+            run: None,
+        })
+    }
+}
+
+impl Occurrences {
+    fn occurrence(&self, end_location: usize, edit_distance: usize) -> anyhow::Result<Occurrence> {
+        let start_location = find_start_location(
+            &self.rev_profile.peq,
+            self.rev_profile.w,
+            self.rev_profile.max_num_blocks,
+            self.rev_profile.query_len,
+            &self.track.target,
+            end_location,
+            edit_distance,
+            &mut Meter::default(),
+        )?;
+        Ok(Occurrence {
+            start_location: start_location.try_into()?,
+            end_location,
+            edit_distance,
+        })
+    }
+}
+
+impl Iterator for Occurrences {
+    type Item = anyhow::Result<Occurrence>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // If band stops to exist, no more occurrences.
+        while !self.track.state.is_empty() {
+            let end_location = self.track.position;
+            let score = match self.track.next_score() {
+                Some(Ok(score)) => score,
+                Some(Err(err)) => return Some(Err(err)),
+                None => break,
+            };
+
+            match score {
+                Some(score) => {
+                    if self.run.is_none_or(|(_, run_score)| score < run_score) {
+                        self.run = Some((end_location, score))
+                    }
+                }
+                None => {
+                    if let Some((end_location, score)) = self.run.take() {
+                        return Some(self.occurrence(end_location, score));
+                    }
+                }
+            }
+        }
+        // Run reaching end of target.
+        self.run
+            .take()
+            .map(|(end_location, score)| self.occurrence(end_location, score))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hw_config(k: usize) -> AlignConfig {
+        AlignConfig {
+            k: Some(k),
+            mode: AlignMode::HW,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_occurrences() {
+        // Exact match, one substitution (AGGT) and one deletion (ACT).
+        let target = "ACGTTTTAGGTTTTACTTTT";
+        let occurrences: Vec<Occurrence> = Alignment::occurrences(hw_config(1), "ACGT", target)
+            .unwrap()
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+
+        assert_eq!(
+            occurrences,
+            [
+                Occurrence {
+                    start_location: 0,
+                    end_location: 3,
+                    edit_distance: 0
+                },
+                Occurrence {
+                    start_location: 7,
+                    end_location: 10,
+                    edit_distance: 1
+                },
+                Occurrence {
+                    start_location: 14,
+                    end_location: 16,
+                    edit_distance: 1
+                },
+            ]
+        );
+        // Alignment::run only reports best score.
+        let best = Alignment::run(hw_config(1), "ACGT", target).unwrap();
+        assert_eq!(best.edit_distance, Some(0));
+        assert_eq!(best.end_locations, Some(vec![3]));
+    }
+
+    #[test]
+    fn test_occurrences_at_target_end() {
+        let occurrences: Vec<Occurrence> = Alignment::occurrences(hw_config(0), "ACGT", "TTTACGT")
+            .unwrap()
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            occurrences,
+            [Occurrence {
+                start_location: 3,
+                end_location: 6,
+                edit_distance: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_k_fraction() {
+        let config = AlignConfig {
+            k_fraction: Some(0.25),
+            ..hw_config(4)
+        };
+        let target = "ACGTTTTAGGTTTTACTTTT";
+        let occurrences = |config| {
+            Alignment::occurrences(config, "ACGT", target)
+                .unwrap()
+                .collect::<anyhow::Result<Vec<_>>>()
+                .unwrap()
+        };
+        // k is a quarter of query length.
+        assert_eq!(occurrences(config.clone()), occurrences(hw_config(1)));
+        assert_eq!(
+            occurrences(AlignConfig { k: None, ..config }),
+            occurrences(hw_config(1))
+        );
+    }
+
+    #[test]
+    fn test_occurrences_invalid_config() {
+        assert!(Alignment::occurrences(AlignConfig::default(), "ACGT", "ACGT").is_err());
+        let config = AlignConfig {
+            k: Some(1),
+            ..Default::default()
+        };
+        assert!(Alignment::occurrences(config, "ACGT", "ACGT").is_err());
+    }
+}
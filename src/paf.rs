@@ -0,0 +1,357 @@
+//! PAF records of pairwise alignments.
+//! * See https://github.com/lh3/miniasm/blob/master/PAF.md
+
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    align::Alignment,
+    cigar::{Cigar, CigarFormat, EditOp},
+    sam::{ClippedPath, MAPQ_UNAVAILABLE},
+};
+
+/// Strand of query relative to target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strand {
+    /// `+`
+    #[default]
+    Forward,
+    /// `-`: Query is aligned as reverse complement.
+    Reverse,
+}
+
+impl Display for Strand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strand::Forward => write!(f, "+"),
+            Strand::Reverse => write!(f, "-"),
+        }
+    }
+}
+
+/// One line of a PAF file.
+/// * Positions are zero-based and intervals half-open.
+/// * Query positions are on forward strand of query, also for [`Strand::Reverse`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PafRecord {
+    /// Name of query.
+    pub query_name: String,
+    /// Length of query.
+    pub query_len: usize,
+    /// Start of aligned part of query.
+    pub query_start: usize,
+    /// End of aligned part of query.
+    pub query_end: usize,
+    /// [`Strand`] of query.
+    pub strand: Strand,
+    /// Name of target.
+    pub target_name: String,
+    /// Length of target.
+    pub target_len: usize,
+    /// Start of aligned part of target.
+    pub target_start: usize,
+    /// End of aligned part of target.
+    pub target_end: usize,
+    /// Number of matching symbols.
+    pub residue_matches: usize,
+    /// Number of columns of alignment, including gaps.
+    pub block_len: usize,
+    /// Mapping quality. 255 if not available.
+    pub mapq: u8,
+    /// `cg:Z:` tag: Extended CIGAR of aligned part, if any.
+    pub cigar: Option<Cigar>,
+    /// `NM:i:` tag: Number of edits of aligned part, if any.
+    pub num_edits: Option<usize>,
+    /// Position of `NM:i:` tag among all optional tags. If `None`, it is written after the others.
+    pub num_edits_tag_idx: Option<usize>,
+    /// Position of `cg:Z:` tag among all optional tags. If `None`, it is written after the others.
+    pub cigar_tag_idx: Option<usize>,
+    /// Other optional tags in order, like `tp:A:P`.
+    /// * `NM:i:` and `cg:Z:` tags are kept in `num_edits` and `cigar` instead.
+    pub tags: Vec<String>,
+}
+
+impl Display for PafRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.query_name,
+            self.query_len,
+            self.query_start,
+            self.query_end,
+            self.strand,
+            self.target_name,
+            self.target_len,
+            self.target_start,
+            self.target_end,
+            self.residue_matches,
+            self.block_len,
+            self.mapq
+        )?;
+        let mut typed = [
+            (
+                self.num_edits_tag_idx,
+                self.num_edits.map(|num_edits| format!("NM:i:{num_edits}")),
+            ),
+            (
+                self.cigar_tag_idx,
+                self.cigar.as_ref().map(|cigar| format!("cg:Z:{cigar}")),
+            ),
+        ];
+        // Tags without position last, in stable order.
+        typed.sort_by_key(|(idx, _)| idx.unwrap_or(usize::MAX));
+        let mut tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+        for (idx, tag) in typed.iter() {
+            if let Some(tag) = tag {
+                let idx = idx.map_or(tags.len(), |idx| std::cmp::min(idx, tags.len()));
+                tags.insert(idx, tag);
+            }
+        }
+        for tag in tags {
+            write!(f, "\t{tag}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PafRecord {
+    type Err = anyhow::Error;
+
+    /// Parse a tab-separated PAF line with at least 12 columns.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.trim_end_matches(['\r', '\n']).split('\t').collect();
+        if fields.len() < 12 {
+            bail!("PAF line has {} columns, not at least 12.", fields.len())
+        }
+        let number = |idx: usize| -> anyhow::Result<usize> {
+            fields[idx].parse().with_context(|| {
+                format!("Invalid number {} in PAF column {}.", fields[idx], idx + 1)
+            })
+        };
+        let strand = match fields[4] {
+            "+" => Strand::Forward,
+            "-" => Strand::Reverse,
+            other => bail!("Invalid strand {other} in PAF."),
+        };
+        let mut record = PafRecord {
+            query_name: fields[0].to_string(),
+            query_len: number(1)?,
+            query_start: number(2)?,
+            query_end: number(3)?,
+            strand,
+            target_name: fields[5].to_string(),
+            target_len: number(6)?,
+            target_start: number(7)?,
+            target_end: number(8)?,
+            residue_matches: number(9)?,
+            block_len: number(10)?,
+            mapq: fields[11]
+                .parse()
+                .with_context(|| format!("Invalid mapping quality {} in PAF.", fields[11]))?,
+            ..Default::default()
+        };
+        if record.query_start > record.query_end || record.query_end > record.query_len {
+            bail!(
+                "Query interval {}..{} is not within length {}.",
+                record.query_start,
+                record.query_end,
+                record.query_len
+            )
+        }
+        if record.target_start > record.target_end || record.target_end > record.target_len {
+            bail!(
+                "Target interval {}..{} is not within length {}.",
+                record.target_start,
+                record.target_end,
+                record.target_len
+            )
+        }
+
+        for (idx, tag) in fields[12..].iter().enumerate() {
+            if let Some(cigar) = tag.strip_prefix("cg:Z:") {
+                record.cigar = Some(cigar.parse()?);
+                record.cigar_tag_idx = Some(idx);
+            } else if let Some(num_edits) = tag.strip_prefix("NM:i:") {
+                record.num_edits = Some(
+                    num_edits
+                        .parse()
+                        .with_context(|| format!("Invalid NM tag {tag}."))?,
+                );
+                record.num_edits_tag_idx = Some(idx);
+            } else {
+                record.tags.push(tag.to_string());
+            }
+        }
+        Ok(record)
+    }
+}
+
+impl Alignment {
+    /// [`PafRecord`] of alignment path of first pair of locations.
+    /// * Insertions and deletions at ends of path are left out of aligned parts, like soft clips.
+    /// * Mapping quality is not available, so it is 255.
+    ///
+    /// # Arguments
+    /// * `query`: Query of alignment, as aligned to forward strand of target.
+    /// * `target`: Whole target of alignment.
+    /// * `query_name`: Name of query.
+    /// * `target_name`: Name of target.
+    /// * `strand`: [`Strand::Reverse`] if query is reverse complement of read. Query positions are then on read.
+    ///
+    /// # Returns
+    /// * [`PafRecord`]. Error if there is no path of [`AlignTask::Path`](crate::task::AlignTask::Path)
+    ///   or it has no aligned symbols.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{
+    ///     align::Alignment,
+    ///     config::AlignConfig,
+    ///     mode::AlignMode,
+    ///     paf::{PafRecord, Strand},
+    ///     task::AlignTask,
+    /// };
+    ///
+    /// let config = AlignConfig {
+    ///     mode: AlignMode::HW,
+    ///     task: AlignTask::Path,
+    ///     ..Default::default()
+    /// };
+    /// let (query, target) = ("ACGTTA", "GGACGATAGG");
+    /// let aln = Alignment::run(config, query, target).unwrap();
+    /// let record = aln
+    ///     .paf_record(query, target, "read", "chr1", Strand::Forward)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     record.to_string(),
+    ///     "read\t6\t0\t6\t+\tchr1\t10\t2\t8\t5\t6\t255\tNM:i:1\tcg:Z:3=1X2="
+    /// );
+    /// assert_eq!(record.to_string().parse::<PafRecord>().unwrap(), record);
+    /// ```
+    pub fn paf_record(
+        &self,
+        query: &str,
+        target: &str,
+        query_name: &str,
+        target_name: &str,
+        strand: Strand,
+    ) -> anyhow::Result<PafRecord> {
+        let (Some(path), Some(start)) = (
+            self.alignment.as_ref(),
+            self.start_locations.as_ref().and_then(|locs| locs.first()),
+        ) else {
+            bail!("No alignment path for PAF record.")
+        };
+
+        let Some(clipped) = ClippedPath::new(path) else {
+            bail!("No aligned symbols for PAF record.")
+        };
+        let query_len = query.chars().count();
+        let (query_start, query_end) = (clipped.query_clips.0, query_len - clipped.query_clips.1);
+        let target_start = usize::try_from(*start)? + clipped.target_offset;
+        let (query_start, query_end) = match strand {
+            Strand::Forward => (query_start, query_end),
+            Strand::Reverse => (query_len - query_end, query_len - query_start),
+        };
+        Ok(PafRecord {
+            query_name: query_name.to_string(),
+            query_len,
+            query_start,
+            query_end,
+            strand,
+            target_name: target_name.to_string(),
+            target_len: target.chars().count(),
+            target_start,
+            target_end: target_start + clipped.target_len(),
+            residue_matches: clipped.count(EditOp::Match),
+            block_len: clipped.aligned.len(),
+            mapq: MAPQ_UNAVAILABLE,
+            cigar: Some(Cigar::from_path(clipped.aligned, CigarFormat::Extended)),
+            num_edits: Some(clipped.aligned.len() - clipped.count(EditOp::Match)),
+            num_edits_tag_idx: Some(0),
+            cigar_tag_idx: Some(1),
+            tags: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+    #[test]
+    fn test_paf_record_clips() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            task: AlignTask::Path,
+            ..Default::default()
+        };
+        // Query ends with insertions.
+        let (query, target) = ("ACGTAAAA", "CCACGTCC");
+        let aln = Alignment::run(config, query, target).unwrap();
+        let record = aln
+            .paf_record(query, target, "read", "chr1", Strand::Forward)
+            .unwrap();
+        assert_eq!((record.query_start, record.query_end), (0, 4));
+        assert_eq!((record.target_start, record.target_end), (2, 6));
+        assert_eq!((record.residue_matches, record.block_len), (4, 4));
+
+        let record = aln
+            .paf_record(query, target, "read", "chr1", Strand::Reverse)
+            .unwrap();
+        assert_eq!((record.query_start, record.query_end), (4, 8));
+
+        let aln = Alignment::run(AlignConfig::default(), query, target).unwrap();
+        assert!(aln
+            .paf_record(query, target, "read", "chr1", Strand::Forward)
+            .is_err());
+    }
+
+    #[test]
+    fn test_paf_parse() {
+        let line =
+            "q1\t100\t10\t90\t-\tt1\t1000\t200\t281\t75\t82\t60\ttp:A:P\tNM:i:7\tcg:Z:40=2D40=\n";
+        let record: PafRecord = line.parse().unwrap();
+        assert_eq!(record.strand, Strand::Reverse);
+        assert_eq!((record.target_start, record.target_end), (200, 281));
+        assert_eq!(record.mapq, 60);
+        assert_eq!(record.num_edits, Some(7));
+        assert_eq!(record.cigar.as_ref().unwrap().to_string(), "40=2D40=");
+        assert_eq!(record.tags, ["tp:A:P"]);
+        assert_eq!(
+            (record.num_edits_tag_idx, record.cigar_tag_idx),
+            (Some(1), Some(2))
+        );
+        // Tags keep their order.
+        assert_eq!(record.to_string(), line.trim_end());
+        assert_eq!(record.to_string().parse::<PafRecord>().unwrap(), record);
+
+        let mut record = record;
+        record.num_edits = Some(8);
+        record.cigar = None;
+        assert_eq!(
+            record.to_string(),
+            "q1\t100\t10\t90\t-\tt1\t1000\t200\t281\t75\t82\t60\ttp:A:P\tNM:i:8"
+        );
+
+        // Typed tags before others, and without position after them.
+        let line = "q1\t100\t10\t90\t+\tt1\t1000\t200\t281\t75\t82\t60\tcg:Z:80=\ttp:A:P\tNM:i:0";
+        let mut record: PafRecord = line.parse().unwrap();
+        assert_eq!(record.to_string(), line);
+        record.cigar_tag_idx = None;
+        assert!(record.to_string().ends_with("\ttp:A:P\tNM:i:0\tcg:Z:80="));
+
+        for invalid in [
+            "q1\t100\t10\t90\t-\tt1\t1000\t200\t281\t75\t82",
+            "q1\t100\t10\t90\t*\tt1\t1000\t200\t281\t75\t82\t60",
+            "q1\t100\t10\t190\t+\tt1\t1000\t200\t281\t75\t82\t60",
+            "q1\t100\t10\t90\t+\tt1\t1000\t200\t281\t75\t82\t600",
+            "q1\t100\t10\t90\t+\tt1\t1000\t200\t281\t75\t82\t60\tcg:Z:3S",
+        ] {
+            assert!(invalid.parse::<PafRecord>().is_err(), "{invalid}");
+        }
+    }
+}
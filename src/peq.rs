@@ -1,9 +1,47 @@
+//! Query profile.
+
 use crate::{
     align::{Word, WORD_SIZE},
     ceil_div,
     equal::EqualityDefinition,
 };
 
+/// Query profile of transformed query with its layout of blocks.
+#[derive(Debug, Clone)]
+pub(crate) struct QueryProfile {
+    /// Peq table. See [`build_peq_table`].
+    pub(crate) peq: Vec<Word>,
+    /// Number of blocks needed to cover the whole query.
+    pub(crate) max_num_blocks: usize,
+    /// Number of redundant cells in last level blocks.
+    pub(crate) w: usize,
+    /// Length of query.
+    pub(crate) query_len: usize,
+}
+
+impl QueryProfile {
+    /// Build query profile for given transformed query and alphabet.
+    pub(crate) fn new(
+        alphabet_length: usize,
+        query: &[usize],
+        equality_def: &EqualityDefinition,
+    ) -> anyhow::Result<Self> {
+        let word_size = usize::try_from(WORD_SIZE)?;
+        let max_num_blocks = ceil_div!(query.len(), word_size);
+        Ok(QueryProfile {
+            peq: build_peq_table(alphabet_length, query, equality_def)?,
+            max_num_blocks,
+            w: max_num_blocks * word_size - query.len(),
+            query_len: query.len(),
+        })
+    }
+
+    /// Get Peq of all blocks for given symbol.
+    pub(crate) fn peq_c(&self, symbol: usize) -> &[Word] {
+        &self.peq[symbol * self.max_num_blocks..(symbol + 1) * self.max_num_blocks]
+    }
+}
+
 /// Build Peq (query profile) table for given transformed query and alphabet.
 /// * Peq is table of dimensions `alphabetLength+1 x maxNumBlocks`.
 /// * Bit `i` of `Peq[s * maxNumBlocks + b]` is `1` if `i`-th symbol from block `b` of query equals symbol `s`, otherwise it is `0`.
@@ -75,6 +113,7 @@ pub fn build_peq_table(
     Ok(peq_table)
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
     use crate::align::transform_sequences;
@@ -90,10 +129,20 @@ mod test {
         assert_eq!(
             table,
             [
-                3002117172780181929,
+                // A
+                12225489209634957737,
+                18446744073709529513,
+                // G
                 434041037028460038,
+                18446744073709487622,
+                // T
                 1157442765409226768,
+                18446744073709490192,
+                // C
                 4629771061636907072,
+                18446744073709502528,
+                // Wildcard
+                18446744073709551615,
                 18446744073709551615
             ]
         )
@@ -0,0 +1,102 @@
+//! Progress reports of long alignments.
+
+use std::{fmt::Debug, sync::Arc};
+
+/// State of an alignment after some target columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressReport {
+    /// Zero-based index of last calculated target column.
+    pub column: usize,
+    /// 0-based indices of first and last block of Ukkonen band. `None` if band stopped existing.
+    pub band: Option<(usize, usize)>,
+    /// Best score so far, if not larger than `k`.
+    /// * [`AlignMode::HW`](crate::mode::AlignMode::HW) and [`AlignMode::SHW`](crate::mode::AlignMode::SHW): Best score of query ending at any column so far.
+    /// * [`AlignMode::NW`](crate::mode::AlignMode::NW): Minimum score of current column, a lower bound of edit distance.
+    pub best_score: Option<usize>,
+}
+
+/// Callback invoked every `interval` target columns by [`Alignment::run`](crate::align::Alignment::run).
+/// * Reports each pass of a retry with a larger `k` from column `0`.
+/// * Not invoked while searching start locations.
+///
+/// ### Example
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use rs_edlib::{align::Alignment, config::AlignConfig, progress::Progress};
+///
+/// let columns = Arc::new(Mutex::new(vec![]));
+/// let reported = columns.clone();
+/// let config = AlignConfig {
+///     progress: Some(Progress::new(100, move |report| {
+///         reported.lock().unwrap().push(report.column)
+///     })),
+///     ..Default::default()
+/// };
+/// let query = str::repeat("ACGT", 100);
+/// Alignment::run(config, &query, &query).unwrap();
+/// assert_eq!(*columns.lock().unwrap(), [99, 199, 299, 399]);
+/// ```
+#[derive(Clone)]
+pub struct Progress {
+    /// Number of target columns between reports. `0` disables reports.
+    pub interval: usize,
+    /// Function called with each report.
+    pub callback: Arc<dyn Fn(&ProgressReport) + Send + Sync>,
+}
+
+impl Progress {
+    /// Initialize a new `Progress`.
+    ///
+    /// # Arguments
+    /// * `interval`: Number of target columns between reports.
+    /// * `callback`: Function called with each report.
+    pub fn new(
+        interval: usize,
+        callback: impl Fn(&ProgressReport) + Send + Sync + 'static,
+    ) -> Self {
+        Progress {
+            interval,
+            callback: Arc::new(callback),
+        }
+    }
+}
+
+impl Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{align::Alignment, config::AlignConfig, mode::AlignMode};
+
+    #[test]
+    fn test_progress_hw() {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let reported = reports.clone();
+        let config = AlignConfig {
+            k: Some(2),
+            mode: AlignMode::HW,
+            progress: Some(Progress::new(4, move |report| {
+                reported.lock().unwrap().push(report.clone())
+            })),
+            ..Default::default()
+        };
+        Alignment::run(config, "ACGT", "TTTTACGATTTT").unwrap();
+        let best_scores: Vec<Option<usize>> = reports
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|report| report.best_score)
+            .collect();
+        assert_eq!(best_scores, [None, Some(1), Some(1)]);
+        assert_eq!(reports.lock().unwrap()[0].band, Some((0, 0)));
+    }
+}
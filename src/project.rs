@@ -0,0 +1,287 @@
+//! Projection of positions between query and target through an alignment path.
+
+use std::ops::Range;
+
+use crate::{align::Alignment, cigar::EditOp};
+
+/// What to return for a position aligned to a gap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GapPolicy {
+    /// No position.
+    #[default]
+    Skip,
+    /// Position of nearest aligned symbol before gap.
+    Previous,
+    /// Position of nearest aligned symbol after gap.
+    Next,
+}
+
+/// Run of matches and mismatches. Diagonal of dynamic programming matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    query_start: usize,
+    target_start: usize,
+    len: usize,
+}
+
+impl Segment {
+    fn start(&self, from_query: bool) -> usize {
+        if from_query {
+            self.query_start
+        } else {
+            self.target_start
+        }
+    }
+}
+
+/// Map of positions between query and target of an alignment path.
+/// * Positions are zero-based within whole query and target.
+/// * Each lookup is a binary search over runs of matches and mismatches, so O(log n) in number of gaps.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{
+///     cigar::EditOp,
+///     project::{GapPolicy, Projection},
+/// };
+///
+/// // Query ACGT, target TTAGT.
+/// let path = [EditOp::Match, EditOp::Insert, EditOp::Match, EditOp::Match];
+/// let projection = Projection::new(&path, 2);
+/// assert_eq!(projection.query_to_target(2, GapPolicy::Skip), Some(3));
+/// assert_eq!(projection.query_to_target(1, GapPolicy::Skip), None);
+/// assert_eq!(projection.query_to_target(1, GapPolicy::Previous), Some(2));
+/// assert_eq!(projection.target_to_query(4, GapPolicy::Skip), Some(3));
+/// assert_eq!(projection.query_interval_to_target(1..3), Some(3..4));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Projection {
+    /// Runs increasing in both query and target.
+    segments: Vec<Segment>,
+    /// Number of query symbols of path.
+    query_len: usize,
+    /// Aligned target positions.
+    target_span: Range<usize>,
+}
+
+impl Projection {
+    /// Build projection of a path.
+    ///
+    /// # Arguments
+    /// * `path`: [`EditOp`]s from start to end of alignment of whole query.
+    /// * `target_start`: Zero-based position in target where alignment starts.
+    pub fn new(path: &[EditOp], target_start: usize) -> Self {
+        let mut segments: Vec<Segment> = vec![];
+        let (mut query_pos, mut target_pos) = (0, target_start);
+        for op in path {
+            match op {
+                EditOp::Match | EditOp::Mismatch => {
+                    match segments.last_mut() {
+                        Some(last)
+                            if last.query_start + last.len == query_pos
+                                && last.target_start + last.len == target_pos =>
+                        {
+                            last.len += 1
+                        }
+                        _ => segments.push(Segment {
+                            query_start: query_pos,
+                            target_start: target_pos,
+                            len: 1,
+                        }),
+                    }
+                    query_pos += 1;
+                    target_pos += 1;
+                }
+                EditOp::Insert => query_pos += 1,
+                EditOp::Delete => target_pos += 1,
+            }
+        }
+        Projection {
+            segments,
+            query_len: query_pos,
+            target_span: target_start..target_pos,
+        }
+    }
+
+    /// Map a position from one sequence to the other.
+    fn project(&self, pos: usize, policy: GapPolicy, from_query: bool) -> Option<usize> {
+        let span = if from_query {
+            0..self.query_len
+        } else {
+            self.target_span.clone()
+        };
+        if !span.contains(&pos) {
+            return None;
+        }
+        // Number of segments starting at or before position.
+        let idx = self
+            .segments
+            .partition_point(|segment| segment.start(from_query) <= pos);
+        let previous = idx.checked_sub(1).map(|idx| &self.segments[idx]);
+        if let Some(segment) = previous {
+            let offset = pos - segment.start(from_query);
+            if offset < segment.len {
+                return Some(segment.start(!from_query) + offset);
+            }
+        }
+        // Position is within a gap.
+        match policy {
+            GapPolicy::Skip => None,
+            GapPolicy::Previous => {
+                previous.map(|segment| segment.start(!from_query) + segment.len - 1)
+            }
+            GapPolicy::Next => self
+                .segments
+                .get(idx)
+                .map(|segment| segment.start(!from_query)),
+        }
+    }
+
+    /// Map a half-open interval from one sequence to the smallest interval covering its aligned positions.
+    fn project_interval(&self, interval: Range<usize>, from_query: bool) -> Option<Range<usize>> {
+        if interval.is_empty() {
+            return None;
+        }
+        let start = self.project(interval.start, GapPolicy::Next, from_query)?;
+        let end = self.project(interval.end - 1, GapPolicy::Previous, from_query)?;
+        // Interval is only within a gap.
+        (start <= end).then_some(start..end + 1)
+    }
+
+    /// Target position aligned to query position.
+    ///
+    /// # Arguments
+    /// * `pos`: Zero-based position in query.
+    /// * `policy`: [`GapPolicy`] if query position is an insertion.
+    ///
+    /// # Returns
+    /// * Zero-based position in target. `None` if outside of alignment.
+    pub fn query_to_target(&self, pos: usize, policy: GapPolicy) -> Option<usize> {
+        self.project(pos, policy, true)
+    }
+
+    /// Query position aligned to target position.
+    ///
+    /// # Arguments
+    /// * `pos`: Zero-based position in target.
+    /// * `policy`: [`GapPolicy`] if target position is a deletion.
+    ///
+    /// # Returns
+    /// * Zero-based position in query. `None` if outside of alignment.
+    pub fn target_to_query(&self, pos: usize, policy: GapPolicy) -> Option<usize> {
+        self.project(pos, policy, false)
+    }
+
+    /// Target interval aligned to a query interval, like an annotation.
+    /// * Gaps at ends of interval are trimmed.
+    ///
+    /// # Arguments
+    /// * `interval`: Half-open zero-based interval of query.
+    ///
+    /// # Returns
+    /// * Half-open interval of target. `None` if no symbol of interval is aligned, or an end is outside of alignment.
+    pub fn query_interval_to_target(&self, interval: Range<usize>) -> Option<Range<usize>> {
+        self.project_interval(interval, true)
+    }
+
+    /// Query interval aligned to a target interval.
+    /// * Gaps at ends of interval are trimmed.
+    ///
+    /// # Arguments
+    /// * `interval`: Half-open zero-based interval of target.
+    ///
+    /// # Returns
+    /// * Half-open interval of query. `None` if no symbol of interval is aligned, or an end is outside of alignment.
+    pub fn target_interval_to_query(&self, interval: Range<usize>) -> Option<Range<usize>> {
+        self.project_interval(interval, false)
+    }
+
+    /// Target positions aligned to many query positions. See [`Projection::query_to_target`].
+    pub fn query_to_target_batch(
+        &self,
+        positions: &[usize],
+        policy: GapPolicy,
+    ) -> Vec<Option<usize>> {
+        positions
+            .iter()
+            .map(|pos| self.query_to_target(*pos, policy))
+            .collect()
+    }
+
+    /// Query positions aligned to many target positions. See [`Projection::target_to_query`].
+    pub fn target_to_query_batch(
+        &self,
+        positions: &[usize],
+        policy: GapPolicy,
+    ) -> Vec<Option<usize>> {
+        positions
+            .iter()
+            .map(|pos| self.target_to_query(*pos, policy))
+            .collect()
+    }
+}
+
+impl Alignment {
+    /// [`Projection`] of alignment path of first pair of locations.
+    ///
+    /// # Returns
+    /// * [`Projection`], if found with [`AlignTask::Path`](crate::task::AlignTask::Path).
+    pub fn projection(&self) -> Option<Projection> {
+        let path = self.alignment.as_ref()?;
+        let start = *self.start_locations.as_ref()?.first()?;
+        Some(Projection::new(path, usize::try_from(start).ok()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+    #[test]
+    fn test_projection_gaps() {
+        // Query:  AC--GTT
+        // Target: ACAAG-T
+        let path = [
+            EditOp::Match,
+            EditOp::Match,
+            EditOp::Delete,
+            EditOp::Delete,
+            EditOp::Match,
+            EditOp::Insert,
+            EditOp::Match,
+        ];
+        let projection = Projection::new(&path, 0);
+        assert_eq!(
+            projection.query_to_target_batch(&[0, 1, 2, 3, 4, 5], GapPolicy::Skip),
+            [Some(0), Some(1), Some(4), None, Some(5), None]
+        );
+        assert_eq!(projection.query_to_target(3, GapPolicy::Previous), Some(4));
+        assert_eq!(projection.query_to_target(3, GapPolicy::Next), Some(5));
+        assert_eq!(
+            projection.target_to_query_batch(&[1, 2, 3, 4], GapPolicy::Next),
+            [Some(1), Some(2), Some(2), Some(2)]
+        );
+        assert_eq!(projection.target_to_query(2, GapPolicy::Previous), Some(1));
+
+        assert_eq!(projection.target_interval_to_query(1..4), Some(1..2));
+        assert_eq!(projection.target_interval_to_query(2..4), None);
+        assert_eq!(projection.query_interval_to_target(0..5), Some(0..6));
+    }
+
+    #[test]
+    fn test_projection_alignment() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            task: AlignTask::Path,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, "ACGT", "TTTACGTTT").unwrap();
+        let projection = aln.projection().unwrap();
+        assert_eq!(projection.query_to_target(0, GapPolicy::Skip), Some(3));
+        assert_eq!(projection.target_to_query(6, GapPolicy::Skip), Some(3));
+        // Outside of alignment.
+        assert_eq!(projection.target_to_query(7, GapPolicy::Previous), None);
+        assert_eq!(projection.query_to_target(4, GapPolicy::Previous), None);
+    }
+}
@@ -0,0 +1,367 @@
+//! SAM records and headers of alignments.
+//! * See http://samtools.github.io/hts-specs/SAMv1.pdf
+
+use std::{fmt::Display, io::Write};
+
+use anyhow::bail;
+
+use crate::{
+    align::Alignment,
+    cigar::{Cigar, CigarFormat, EditOp},
+};
+
+/// Flag of a read without alignment.
+pub const FLAG_UNMAPPED: u16 = 0x4;
+/// Flag of a read aligned as reverse complement.
+pub const FLAG_REVERSE: u16 = 0x10;
+/// Mapping quality of an aligned read, as quality is not available.
+pub const MAPQ_UNAVAILABLE: u8 = 255;
+
+/// One alignment line of a SAM file.
+/// * Query is read and target is reference.
+/// * Mate fields are always `*`, `0` and `0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SamRecord {
+    /// `QNAME`.
+    pub query_name: String,
+    /// `FLAG`, like [`FLAG_REVERSE`].
+    pub flag: u16,
+    /// `RNAME`. `*` if unmapped.
+    pub reference_name: String,
+    /// `POS`: One-based position of first aligned target symbol. `0` if unmapped.
+    pub pos: usize,
+    /// `MAPQ`.
+    pub mapq: u8,
+    /// Number of query symbols soft clipped before and after [`SamRecord::cigar`].
+    pub soft_clips: (usize, usize),
+    /// Extended CIGAR of aligned part of query. `None` if unmapped.
+    pub cigar: Option<Cigar>,
+    /// `SEQ`: Whole query, including soft clips.
+    pub sequence: String,
+    /// `QUAL`: Phred+33 qualities of query, if any.
+    pub qualities: Option<String>,
+    /// `NM` tag: Number of edits of aligned part. `None` if unmapped.
+    pub num_edits: Option<usize>,
+    /// `MD` tag: Matched lengths, mismatched and deleted target symbols. `None` if unmapped.
+    pub mismatch_string: Option<String>,
+}
+
+impl SamRecord {
+    /// CIGAR string with soft clips, like `2S3=1X`. `*` if unmapped.
+    pub fn cigar_string(&self) -> String {
+        let Some(cigar) = self.cigar.as_ref() else {
+            return "*".to_string();
+        };
+        let clip = |len: usize| {
+            if len > 0 {
+                format!("{len}S")
+            } else {
+                String::new()
+            }
+        };
+        format!(
+            "{}{cigar}{}",
+            clip(self.soft_clips.0),
+            clip(self.soft_clips.1)
+        )
+    }
+}
+
+impl Display for SamRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_star = |field: &str| {
+            if field.is_empty() {
+                "*".to_string()
+            } else {
+                field.to_string()
+            }
+        };
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t*\t0\t0\t{}\t{}",
+            or_star(&self.query_name),
+            self.flag,
+            or_star(&self.reference_name),
+            self.pos,
+            self.mapq,
+            self.cigar_string(),
+            or_star(&self.sequence),
+            or_star(self.qualities.as_deref().unwrap_or_default()),
+        )?;
+        if let Some(num_edits) = self.num_edits {
+            write!(f, "\tNM:i:{num_edits}")?;
+        }
+        if let Some(mismatch_string) = self.mismatch_string.as_ref() {
+            write!(f, "\tMD:Z:{mismatch_string}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Path without gaps before first and after last aligned pair of symbols.
+pub(crate) struct ClippedPath<'a> {
+    /// [`EditOp`]s from first to last aligned pair.
+    pub aligned: &'a [EditOp],
+    /// Number of query symbols before and after aligned part.
+    pub query_clips: (usize, usize),
+    /// Number of target symbols before aligned part.
+    pub target_offset: usize,
+}
+
+impl<'a> ClippedPath<'a> {
+    /// Clip a path.
+    ///
+    /// # Returns
+    /// * [`ClippedPath`]. `None` if path has no match or mismatch.
+    pub(crate) fn new(path: &'a [EditOp]) -> Option<Self> {
+        let is_aligned = |op: &EditOp| matches!(op, EditOp::Match | EditOp::Mismatch);
+        let first = path.iter().position(is_aligned)?;
+        let last = path.iter().rposition(is_aligned)?;
+        let (head, tail) = (&path[..first], &path[last + 1..]);
+        let count = |ops: &[EditOp], op: EditOp| ops.iter().filter(|other| **other == op).count();
+        Some(ClippedPath {
+            aligned: &path[first..=last],
+            query_clips: (count(head, EditOp::Insert), count(tail, EditOp::Insert)),
+            target_offset: count(head, EditOp::Delete),
+        })
+    }
+
+    /// Number of an operation within aligned part.
+    pub(crate) fn count(&self, op: EditOp) -> usize {
+        self.aligned.iter().filter(|other| **other == op).count()
+    }
+
+    /// Number of target symbols of aligned part.
+    pub(crate) fn target_len(&self) -> usize {
+        self.aligned.len() - self.count(EditOp::Insert)
+    }
+}
+
+/// `MD` tag of an aligned path.
+/// * Matches of added equalities are counted as matches.
+///
+/// # Arguments
+/// * `path`: [`EditOp`]s from first to last aligned target symbol.
+/// * `target`: Aligned part of target.
+fn mismatch_string(path: &[EditOp], target: &[char]) -> String {
+    let mut md = String::new();
+    let mut num_matches = 0;
+    let mut target_chars = target.iter();
+    let mut in_deletion = false;
+    for op in path {
+        match op {
+            EditOp::Match => {
+                target_chars.next();
+                num_matches += 1;
+                in_deletion = false;
+            }
+            EditOp::Mismatch => {
+                md.push_str(&num_matches.to_string());
+                md.extend(target_chars.next());
+                num_matches = 0;
+                in_deletion = false;
+            }
+            EditOp::Delete => {
+                if !in_deletion {
+                    md.push_str(&format!("{num_matches}^"));
+                    num_matches = 0;
+                    in_deletion = true;
+                }
+                md.extend(target_chars.next());
+            }
+            EditOp::Insert => (),
+        }
+    }
+    md.push_str(&num_matches.to_string());
+    md
+}
+
+/// Write SAM header with one `@SQ` line per target.
+///
+/// # Arguments
+/// * `writer`: Writer of SAM.
+/// * `references`: Name and length of each target.
+pub fn write_sam_header(
+    mut writer: impl Write,
+    references: &[(&str, usize)],
+) -> anyhow::Result<()> {
+    writeln!(writer, "@HD\tVN:1.6\tSO:unsorted")?;
+    for (name, len) in references {
+        writeln!(writer, "@SQ\tSN:{name}\tLN:{len}")?;
+    }
+    writeln!(
+        writer,
+        "@PG\tID:{0}\tPN:{0}\tVN:{1}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    Ok(())
+}
+
+impl Alignment {
+    /// [`SamRecord`] of alignment path of first pair of locations.
+    /// * Insertions at ends of path are soft clipped, and deletions at ends are left out of alignment.
+    /// * Read is unmapped if no alignment within threshold was found, or it has no aligned symbols.
+    /// * CIGAR is extended. Use [`Cigar::to_format`] for `M` operations.
+    ///
+    /// # Arguments
+    /// * `query`: Query of alignment, as aligned to forward strand of target.
+    /// * `target`: Whole target of alignment.
+    /// * `query_name`: Name of query.
+    /// * `reference_name`: Name of target.
+    /// * `qualities`: Phred+33 qualities of query, in order of `query`.
+    /// * `reverse`: If query is reverse complement of read.
+    ///
+    /// # Returns
+    /// * [`SamRecord`]. Error if a distance was found without [`AlignTask::Path`](crate::task::AlignTask::Path),
+    ///   or qualities are not as long as query.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, config::AlignConfig, mode::AlignMode, task::AlignTask};
+    ///
+    /// let config = AlignConfig {
+    ///     mode: AlignMode::HW,
+    ///     task: AlignTask::Path,
+    ///     ..Default::default()
+    /// };
+    /// let (query, target) = ("ACGTAAAA", "CCACGTCC");
+    /// let aln = Alignment::run(config, query, target).unwrap();
+    /// let record = aln
+    ///     .sam_record(query, target, "read", "chr1", None, false)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     record.to_string(),
+    ///     "read\t0\tchr1\t3\t255\t4=4S\t*\t0\t0\tACGTAAAA\t*\tNM:i:0\tMD:Z:4"
+    /// );
+    /// ```
+    pub fn sam_record(
+        &self,
+        query: &str,
+        target: &str,
+        query_name: &str,
+        reference_name: &str,
+        qualities: Option<&str>,
+        reverse: bool,
+    ) -> anyhow::Result<SamRecord> {
+        let query_len = query.chars().count();
+        if let Some(qualities) = qualities.filter(|qual| qual.chars().count() != query_len) {
+            bail!(
+                "{} qualities for query of length {query_len}.",
+                qualities.chars().count()
+            )
+        }
+        let mut record = SamRecord {
+            query_name: query_name.to_string(),
+            flag: FLAG_UNMAPPED | if reverse { FLAG_REVERSE } else { 0 },
+            sequence: query.to_string(),
+            qualities: qualities.map(|qual| qual.to_string()),
+            ..Default::default()
+        };
+        if self.edit_distance.is_none() {
+            return Ok(record);
+        }
+        let (Some(path), Some(start)) = (
+            self.alignment.as_ref(),
+            self.start_locations.as_ref().and_then(|locs| locs.first()),
+        ) else {
+            bail!("No alignment path for SAM record.")
+        };
+
+        let Some(clipped) = ClippedPath::new(path) else {
+            return Ok(record);
+        };
+        let target_start = usize::try_from(*start)? + clipped.target_offset;
+        let aligned_target: Vec<char> = target
+            .chars()
+            .skip(target_start)
+            .take(clipped.target_len())
+            .collect();
+
+        record.flag &= !FLAG_UNMAPPED;
+        record.reference_name = reference_name.to_string();
+        record.pos = target_start + 1;
+        record.mapq = MAPQ_UNAVAILABLE;
+        record.soft_clips = clipped.query_clips;
+        record.cigar = Some(Cigar::from_path(clipped.aligned, CigarFormat::Extended));
+        record.num_edits = Some(clipped.aligned.len() - clipped.count(EditOp::Match));
+        record.mismatch_string = Some(mismatch_string(clipped.aligned, &aligned_target));
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+    #[test]
+    fn test_mismatch_string() {
+        let path = "2=1X1D1=2D1I1X"
+            .parse::<Cigar>()
+            .unwrap()
+            .to_path()
+            .unwrap();
+        assert_eq!(
+            mismatch_string(&path, &"ACGTACGT".chars().collect::<Vec<char>>()),
+            "2G0^T1^CG0T0"
+        );
+        assert_eq!(mismatch_string(&[], &[]), "0");
+    }
+
+    #[test]
+    fn test_sam_record() {
+        let config = AlignConfig {
+            mode: AlignMode::SHW,
+            task: AlignTask::Path,
+            ..Default::default()
+        };
+        let (query, target) = ("ACTTTTC", "ACGTTTTCGG");
+        let aln = Alignment::run(config, query, target).unwrap();
+        let record = aln
+            .sam_record(query, target, "read", "chr1", Some("IIIIIII"), true)
+            .unwrap();
+        assert_eq!(record.flag, FLAG_REVERSE);
+        assert_eq!(record.pos, 1);
+        assert_eq!(record.cigar_string(), "2=1D5=");
+        assert_eq!(record.num_edits, Some(1));
+        assert_eq!(record.mismatch_string.as_deref(), Some("2^G5"));
+        assert!(record
+            .to_string()
+            .ends_with("ACTTTTC\tIIIIIII\tNM:i:1\tMD:Z:2^G5"));
+
+        assert!(aln
+            .sam_record(query, target, "read", "chr1", Some("III"), true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sam_record_unmapped() {
+        let config = AlignConfig {
+            task: AlignTask::Path,
+            k: Some(1),
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, "ACGT", "TTTT").unwrap();
+        let record = aln
+            .sam_record("ACGT", "TTTT", "read", "chr1", None, false)
+            .unwrap();
+        assert_eq!(record.to_string(), "read\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\t*");
+
+        let aln = Alignment::run(AlignConfig::default(), "ACGT", "TTTT").unwrap();
+        assert!(aln
+            .sam_record("ACGT", "TTTT", "read", "chr1", None, false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sam_header() {
+        let mut header = vec![];
+        write_sam_header(&mut header, &[("chr1", 100), ("chr2", 50)]).unwrap();
+        let header = String::from_utf8(header).unwrap();
+        let lines: Vec<&str> = header.lines().collect();
+        assert_eq!(lines[0], "@HD\tVN:1.6\tSO:unsorted");
+        assert_eq!(lines[2], "@SQ\tSN:chr2\tLN:50");
+        assert!(lines[3].starts_with("@PG\tID:rs_edlib"));
+    }
+}
@@ -0,0 +1,373 @@
+//! Simulation of sequencing reads with known alignments.
+
+use anyhow::bail;
+
+use crate::cigar::EditOp;
+
+/// Seedable pseudo-random number generator (SplitMix64).
+/// * Same seed always gives same numbers on every platform.
+/// * Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Prng {
+    state: u64,
+}
+
+impl Prng {
+    /// Initialize a new `Prng` from a seed.
+    pub fn new(seed: u64) -> Self {
+        Prng { state: seed }
+    }
+
+    /// Next random 64-bit number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `0..n`. `n` must be larger than `0`.
+    pub fn below(&mut self, n: usize) -> usize {
+        // Multiply-shift avoids the bias of a modulo for small n.
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+
+    /// Random number in `0.0..1.0`.
+    pub fn uniform(&mut self) -> f64 {
+        // 53 bits of mantissa.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Random event with given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.uniform() < probability
+    }
+}
+
+/// Burst of many errors in a short stretch, like in nanopore reads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Burst {
+    /// Probability of a burst starting at each source position.
+    pub rate: f64,
+    /// Mean number of source positions in burst.
+    pub mean_len: usize,
+    /// Total probability of an error at each position within burst.
+    /// * Split between substitutions, insertions and deletions like the rates of [`ErrorProfile`].
+    pub error_rate: f64,
+}
+
+/// Rates of errors at each source position.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ErrorProfile {
+    /// Probability of substituting a source symbol with another symbol.
+    pub substitution: f64,
+    /// Probability of inserting a random symbol before a source symbol.
+    pub insertion: f64,
+    /// Probability of deleting a source symbol.
+    pub deletion: f64,
+    /// Factor of insertion and deletion rates within homopolymer runs, like `AAAA`.
+    /// * Insertions within runs repeat the symbol of run, changing its length.
+    /// * `0.0` or `1.0` for no bias.
+    pub homopolymer_bias: f64,
+    /// Error bursts, if any.
+    pub burst: Option<Burst>,
+}
+
+impl ErrorProfile {
+    /// Profile with only uniform errors of equal rates summing to `error_rate`.
+    pub fn uniform(error_rate: f64) -> Self {
+        ErrorProfile {
+            substitution: error_rate / 3.0,
+            insertion: error_rate / 3.0,
+            deletion: error_rate / 3.0,
+            ..Default::default()
+        }
+    }
+
+    /// Rates of substitution, insertion and deletion at a position.
+    fn rates(&self, in_burst: bool, in_homopolymer: bool) -> (f64, f64, f64) {
+        let (mut substitution, mut insertion, mut deletion) =
+            (self.substitution, self.insertion, self.deletion);
+        if in_homopolymer && self.homopolymer_bias > 0.0 {
+            insertion *= self.homopolymer_bias;
+            deletion *= self.homopolymer_bias;
+        }
+        if let (true, Some(burst)) = (in_burst, &self.burst) {
+            let total = substitution + insertion + deletion;
+            // Keep proportions of rates. Errors are equally likely if there are no rates.
+            let (scale, default) = if total > 0.0 {
+                (burst.error_rate / total, 0.0)
+            } else {
+                (0.0, burst.error_rate / 3.0)
+            };
+            substitution = substitution * scale + default;
+            insertion = insertion * scale + default;
+            deletion = deletion * scale + default;
+        }
+        (substitution, insertion, deletion)
+    }
+}
+
+/// Read simulated from a target with its true alignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedRead {
+    /// Mutated read. Query of alignment.
+    pub read: String,
+    /// Target the read was simulated from.
+    pub target: String,
+    /// Zero-based position in target where source of read starts.
+    pub start_location: isize,
+    /// Zero-based position in target where source of read ends. `start_location - 1` if source is empty.
+    pub end_location: isize,
+    /// True [`EditOp`]s of read against target between locations.
+    /// * Same semantics as [`Alignment::alignment`](crate::align::Alignment::alignment): [`EditOp::Insert`] consumes read and [`EditOp::Delete`] consumes target.
+    /// * Number of errors is an upper bound of edit distance. An optimal alignment may be shorter or end elsewhere.
+    pub path: Vec<EditOp>,
+}
+
+impl SimulatedRead {
+    /// Number of substitutions, insertions and deletions in path.
+    pub fn num_errors(&self) -> usize {
+        self.path.iter().filter(|op| **op != EditOp::Match).count()
+    }
+}
+
+/// Simulator of reads with an [`ErrorProfile`].
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{
+///     align::Alignment,
+///     config::AlignConfig,
+///     mode::AlignMode,
+///     simulate::{ErrorProfile, Simulator},
+/// };
+///
+/// let mut simulator = Simulator::new("ACGT", ErrorProfile::uniform(0.1), 42).unwrap();
+/// let sim = simulator.embedded_read(200, 100).unwrap();
+///
+/// let config = AlignConfig {
+///     mode: AlignMode::HW,
+///     ..Default::default()
+/// };
+/// let aln = Alignment::run(config, &sim.read, &sim.target).unwrap();
+/// assert!(aln.edit_distance.unwrap() <= sim.num_errors());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    /// Symbols of random sequences, insertions and substitutions.
+    pub alphabet: Vec<char>,
+    /// Rates of errors.
+    pub profile: ErrorProfile,
+    /// Random number generator.
+    pub rng: Prng,
+}
+
+impl Simulator {
+    /// Initialize a new `Simulator`.
+    ///
+    /// # Arguments
+    /// * `alphabet`: Symbols of random sequences, insertions and substitutions.
+    /// * `profile`: Rates of errors.
+    /// * `seed`: Seed of random number generator.
+    ///
+    /// # Returns
+    /// * Error if alphabet is empty or a rate is not a probability.
+    pub fn new(alphabet: &str, profile: ErrorProfile, seed: u64) -> anyhow::Result<Self> {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        if alphabet.is_empty() {
+            bail!("Empty alphabet.")
+        }
+        let mut rates = vec![profile.substitution, profile.insertion, profile.deletion];
+        if let Some(burst) = &profile.burst {
+            rates.extend([burst.rate, burst.error_rate]);
+        }
+        if let Some(rate) = rates.iter().find(|rate| !(0.0..=1.0).contains(*rate)) {
+            bail!("Rate {rate} is not within 0.0 and 1.0.")
+        }
+        if profile.homopolymer_bias.is_nan() || profile.homopolymer_bias < 0.0 {
+            bail!("Invalid homopolymer bias {}.", profile.homopolymer_bias)
+        }
+        Ok(Simulator {
+            alphabet,
+            profile,
+            rng: Prng::new(seed),
+        })
+    }
+
+    /// Random sequence of symbols of alphabet.
+    pub fn random_sequence(&mut self, len: usize) -> String {
+        (0..len).map(|_| self.random_symbol()).collect()
+    }
+
+    /// Simulate a read of a whole source sequence.
+    ///
+    /// # Arguments
+    /// * `source`: Sequence to mutate.
+    ///
+    /// # Returns
+    /// * [`SimulatedRead`] with source as target.
+    pub fn read(&mut self, source: &str) -> SimulatedRead {
+        let source: Vec<char> = source.chars().collect();
+        let mut read = String::with_capacity(source.len());
+        let mut path = Vec::with_capacity(source.len());
+        let mut burst_remaining = 0;
+
+        for (pos, symbol) in source.iter().copied().enumerate() {
+            if burst_remaining == 0 {
+                if let Some(burst) = &self.profile.burst {
+                    if self.rng.chance(burst.rate) {
+                        // Uniform length with mean of burst length.
+                        burst_remaining = 1 + self.rng.below(2 * burst.mean_len.max(1) - 1);
+                    }
+                }
+            }
+            let in_homopolymer = pos
+                .checked_sub(1)
+                .is_some_and(|prev| source[prev] == symbol)
+                || source.get(pos + 1) == Some(&symbol);
+            let (substitution, insertion, deletion) =
+                self.profile.rates(burst_remaining > 0, in_homopolymer);
+            burst_remaining = burst_remaining.saturating_sub(1);
+
+            if self.rng.chance(insertion) {
+                let inserted = if in_homopolymer {
+                    symbol
+                } else {
+                    self.random_symbol()
+                };
+                read.push(inserted);
+                path.push(EditOp::Insert);
+            }
+            let draw = self.rng.uniform();
+            if draw < deletion {
+                path.push(EditOp::Delete);
+            } else if draw < deletion + substitution && self.alphabet.iter().any(|s| *s != symbol) {
+                let substituted = loop {
+                    let substituted = self.random_symbol();
+                    if substituted != symbol {
+                        break substituted;
+                    }
+                };
+                read.push(substituted);
+                path.push(EditOp::Mismatch);
+            } else {
+                read.push(symbol);
+                path.push(EditOp::Match);
+            }
+        }
+        SimulatedRead {
+            read,
+            start_location: 0,
+            end_location: source.len() as isize - 1,
+            target: source.into_iter().collect(),
+            path,
+        }
+    }
+
+    /// Simulate a read of a random source embedded within random flanks, for [`AlignMode::HW`](crate::mode::AlignMode::HW).
+    ///
+    /// # Arguments
+    /// * `source_len`: Length of random source of read.
+    /// * `flank_len`: Length of random sequence before and after source in target.
+    ///
+    /// # Returns
+    /// * [`SimulatedRead`] with locations of source within target.
+    pub fn embedded_read(
+        &mut self,
+        source_len: usize,
+        flank_len: usize,
+    ) -> anyhow::Result<SimulatedRead> {
+        let source = self.random_sequence(source_len);
+        let prefix = self.random_sequence(flank_len);
+        let suffix = self.random_sequence(flank_len);
+        let sim = self.read(&source);
+        let start_location = isize::try_from(flank_len)?;
+        Ok(SimulatedRead {
+            target: format!("{prefix}{source}{suffix}"),
+            start_location,
+            end_location: start_location + sim.end_location,
+            ..sim
+        })
+    }
+
+    fn random_symbol(&mut self) -> char {
+        self.alphabet[self.rng.below(self.alphabet.len())]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{align::Alignment, config::AlignConfig, mode::AlignMode};
+
+    /// Number of read and target symbols consumed by path.
+    fn apply_path(sim: &SimulatedRead) -> (usize, usize) {
+        let (mut num_read, mut num_target) = (0, 0);
+        for op in sim.path.iter() {
+            match op {
+                EditOp::Match | EditOp::Mismatch => {
+                    num_read += 1;
+                    num_target += 1;
+                }
+                EditOp::Insert => num_read += 1,
+                EditOp::Delete => num_target += 1,
+            }
+        }
+        (num_read, num_target)
+    }
+
+    #[test]
+    fn test_prng_seeded() {
+        let (mut rng_a, mut rng_b) = (Prng::new(7), Prng::new(7));
+        let nums: Vec<u64> = (0..5).map(|_| rng_a.next_u64()).collect();
+        assert_eq!(nums, (0..5).map(|_| rng_b.next_u64()).collect::<Vec<u64>>());
+        assert_ne!(Prng::new(8).next_u64(), nums[0]);
+        assert!((0..1000).all(|_| rng_a.below(3) < 3));
+    }
+
+    #[test]
+    fn test_simulate_no_errors() {
+        let mut simulator = Simulator::new("ACGT", ErrorProfile::default(), 1).unwrap();
+        let sim = simulator.read("ACGTTA");
+        assert_eq!(sim.read, "ACGTTA");
+        assert_eq!(sim.num_errors(), 0);
+        assert_eq!((sim.start_location, sim.end_location), (0, 5));
+    }
+
+    #[test]
+    fn test_simulate_path_consistent() {
+        let profile = ErrorProfile {
+            homopolymer_bias: 4.0,
+            burst: Some(Burst {
+                rate: 0.01,
+                mean_len: 10,
+                error_rate: 0.5,
+            }),
+            ..ErrorProfile::uniform(0.05)
+        };
+        let mut simulator = Simulator::new("ACGT", profile, 3).unwrap();
+        for _ in 0..20 {
+            let sim = simulator.embedded_read(300, 50).unwrap();
+            assert_eq!(sim.target.len(), 400);
+            let (num_read, num_target) = apply_path(&sim);
+            assert_eq!(num_read, sim.read.len());
+            assert_eq!(num_target, 300);
+            assert_eq!(sim.end_location - sim.start_location + 1, 300);
+
+            let config = AlignConfig {
+                mode: AlignMode::HW,
+                ..Default::default()
+            };
+            let aln = Alignment::run(config, &sim.read, &sim.target).unwrap();
+            assert!(aln.edit_distance.unwrap() <= sim.num_errors());
+        }
+    }
+
+    #[test]
+    fn test_simulate_invalid() {
+        assert!(Simulator::new("", ErrorProfile::default(), 0).is_err());
+        assert!(Simulator::new("ACGT", ErrorProfile::uniform(4.0), 0).is_err());
+    }
+}
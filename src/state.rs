@@ -0,0 +1,272 @@
+//! Low-level stepping of Myers' bit-vector algorithm one target column at a time.
+
+use anyhow::bail;
+
+use crate::{
+    align::{transform_query, MAX_UCHAR},
+    block::Block,
+    column::ColumnState,
+    config::AlignConfig,
+    equal::EqualityDefinition,
+    peq::QueryProfile,
+};
+
+/// State of the dynamic programming matrix of a fixed query after some target columns.
+/// * Wraps the [`Block`]s and Ukkonen band of the current column.
+/// * Target is pushed one character at a time, so it can come from any traversal (tries, graphs, backtracking search).
+/// * Alphabet is fixed by query and added equalities. Other target characters never match.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{config::AlignConfig, state::MyersState};
+///
+/// let config = AlignConfig {
+///     k: Some(2),
+///     ..Default::default()
+/// };
+/// let mut state = MyersState::new(config, "kitten").unwrap();
+/// state.push_all(b"sit").unwrap();
+///
+/// // Branch off with different endings.
+/// let snapshot = state.snapshot();
+/// state.push_all(b"ting").unwrap();
+/// // Edit distance of 3 is larger than k.
+/// assert_eq!(state.score(), None);
+///
+/// state.restore(&snapshot);
+/// state.push_all(b"ten").unwrap();
+/// assert_eq!(state.score(), Some(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MyersState {
+    /// Transformed symbol of each character.
+    letter_idx: [usize; MAX_UCHAR],
+    /// Transformed symbol of characters outside of alphabet.
+    unknown_idx: usize,
+    profile: QueryProfile,
+    state: ColumnState,
+}
+
+/// Saved band of a [`MyersState`].
+/// * Only blocks within band are saved, as blocks outside of band are reinitialized before use.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    blocks: Vec<Block>,
+    first_block_idx: isize,
+    last_block_idx: isize,
+    num_columns: usize,
+}
+
+impl MyersState {
+    /// Initialize a new `MyersState` before first target column.
+    ///
+    /// # Arguments
+    /// * `config`: [`AlignConfig`] configuration.
+    ///     * `k` and `k_fraction` limit band. If neither is set, the band is never reduced.
+    ///     * `mode` sets gap before query. [`AlignMode::NW`](crate::mode::AlignMode::NW) is the same as [`AlignMode::SHW`](crate::mode::AlignMode::SHW) column-wise.
+    ///     * `task` is ignored.
+    /// * `query`: Fixed sequence of rows.
+    pub fn new(config: AlignConfig, query: impl AsRef<str>) -> anyhow::Result<Self> {
+        let (alphabet, letter_idx, transformed_query) =
+            transform_query(query.as_ref(), &config.added_equalities)?;
+        if transformed_query.is_empty() {
+            bail!("Query is empty.")
+        }
+        let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
+        let alphabet_len = alphabet.chars().count();
+        let profile = QueryProfile::new(alphabet_len, &transformed_query, &equality_def)?;
+        // Practically unbounded.
+        let k = config
+            .threshold(transformed_query.len())?
+            .unwrap_or(usize::try_from(u32::MAX)?);
+        let state = ColumnState::new(
+            profile.max_num_blocks,
+            profile.w,
+            profile.query_len,
+            k,
+            &config.mode,
+        )?;
+        Ok(MyersState {
+            letter_idx,
+            unknown_idx: alphabet_len - 1,
+            profile,
+            state,
+        })
+    }
+
+    /// Calculate next target column.
+    ///
+    /// # Arguments
+    /// * `symbol`: ASCII character of target.
+    pub fn push(&mut self, symbol: u8) -> anyhow::Result<()> {
+        self.push_char(char::from(symbol))
+    }
+
+    /// Calculate next target column of any character.
+    /// * Characters outside of alphabet never match.
+    ///
+    /// # Arguments
+    /// * `chr`: Character of target.
+    pub fn push_char(&mut self, chr: char) -> anyhow::Result<()> {
+        let c = self
+            .letter_idx
+            .get(chr as usize)
+            .copied()
+            .unwrap_or(self.unknown_idx);
+        self.state.advance(self.profile.peq_c(c))
+    }
+
+    /// Calculate next target columns.
+    ///
+    /// # Arguments
+    /// * `symbols`: ASCII characters of target.
+    pub fn push_all(&mut self, symbols: &[u8]) -> anyhow::Result<()> {
+        for symbol in symbols {
+            self.push(*symbol)?
+        }
+        Ok(())
+    }
+
+    /// Score of last row of current column. Edit distance of query to target so far.
+    ///
+    /// # Returns
+    /// * Score, if not larger than `k`.
+    pub fn score(&self) -> Option<usize> {
+        self.state.last_row_score()
+    }
+
+    /// Minimum score of all rows of current column, including the gap before query.
+    /// * If `None`, no further columns can have a score within `k`.
+    ///
+    /// # Returns
+    /// * Score, if not larger than `k`.
+    pub fn min_score(&self) -> Option<usize> {
+        self.state.min_score()
+    }
+
+    /// Number of target columns calculated.
+    pub fn num_columns(&self) -> usize {
+        self.state.num_columns
+    }
+
+    /// Length of query.
+    pub fn query_len(&self) -> usize {
+        self.profile.query_len
+    }
+
+    /// Threshold number of differences.
+    pub fn k(&self) -> usize {
+        self.state.k as usize
+    }
+
+    /// 0-based indices of first and last [`Block`] within band. `None` if band stopped existing.
+    pub fn band(&self) -> Option<(usize, usize)> {
+        (!self.state.is_empty()).then_some((
+            self.state.first_block_idx as usize,
+            self.state.last_block_idx as usize,
+        ))
+    }
+
+    /// [`Block`]s of current column within band.
+    pub fn blocks(&self) -> &[Block] {
+        self.band()
+            .map_or(&[], |(first, last)| &self.state.blocks[first..=last])
+    }
+
+    /// Save band of current column.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            blocks: self.blocks().to_vec(),
+            first_block_idx: self.state.first_block_idx,
+            last_block_idx: self.state.last_block_idx,
+            num_columns: self.state.num_columns,
+        }
+    }
+
+    /// Save band of current column into an existing [`Snapshot`], reusing its memory.
+    pub fn snapshot_into(&self, snapshot: &mut Snapshot) {
+        snapshot.blocks.clear();
+        snapshot.blocks.extend_from_slice(self.blocks());
+        snapshot.first_block_idx = self.state.first_block_idx;
+        snapshot.last_block_idx = self.state.last_block_idx;
+        snapshot.num_columns = self.state.num_columns;
+    }
+
+    /// Restore band saved by [`MyersState::snapshot`] of this state.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        if let Ok(first_block_idx) = usize::try_from(snapshot.first_block_idx) {
+            self.state.blocks[first_block_idx..first_block_idx + snapshot.blocks.len()]
+                .clone_from_slice(&snapshot.blocks);
+        }
+        self.state.first_block_idx = snapshot.first_block_idx;
+        self.state.last_block_idx = snapshot.last_block_idx;
+        self.state.num_columns = snapshot.num_columns;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mode::AlignMode;
+
+    #[test]
+    fn test_state_nw() {
+        let mut state = MyersState::new(AlignConfig::default(), "ACGT").unwrap();
+        // Empty target.
+        assert_eq!(state.score(), Some(4));
+        let scores: Vec<Option<usize>> = b"ACCGT"
+            .iter()
+            .map(|symbol| {
+                state.push(*symbol).unwrap();
+                state.score()
+            })
+            .collect();
+        assert_eq!(scores, [Some(3), Some(2), Some(2), Some(2), Some(1)]);
+        assert_eq!(state.num_columns(), 5);
+    }
+
+    #[test]
+    fn test_state_k() {
+        let config = AlignConfig {
+            k: Some(1),
+            ..Default::default()
+        };
+        let mut state = MyersState::new(config, "ACGT").unwrap();
+        state.push_all(b"TTTT").unwrap();
+        assert_eq!(state.score(), None);
+        assert_eq!(state.min_score(), None);
+    }
+
+    #[test]
+    fn test_state_k_fraction() {
+        let config = AlignConfig {
+            k_fraction: Some(0.25),
+            ..Default::default()
+        };
+        let mut state = MyersState::new(config, "ACGT").unwrap();
+        assert_eq!(state.k(), 1);
+        state.push_all(b"TTTT").unwrap();
+        assert_eq!(state.min_score(), None);
+    }
+
+    #[test]
+    fn test_state_hw_long_query() {
+        let query = str::repeat("ACGT", 50);
+        let config = AlignConfig {
+            k: Some(5),
+            mode: AlignMode::HW,
+            ..Default::default()
+        };
+        let mut state = MyersState::new(config, &query).unwrap();
+        state.push_all(b"TTTTTTTT").unwrap();
+        let snapshot = state.snapshot();
+
+        state.push_all(query.as_bytes()).unwrap();
+        assert_eq!(state.score(), Some(0));
+
+        state.restore(&snapshot);
+        assert_eq!(state.num_columns(), 8);
+        state.push_all(&query.as_bytes()[2..]).unwrap();
+        assert_eq!(state.score(), Some(2));
+    }
+}
@@ -0,0 +1,189 @@
+//! Summary statistics of an alignment path.
+
+use crate::{align::Alignment, cigar::EditOp, sam::ClippedPath};
+
+/// Summary numbers of the path of an [`Alignment`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlignmentStats {
+    /// Number of columns of alignment. Length of path.
+    pub aligned_len: usize,
+    /// Number of [`EditOp::Match`]es.
+    pub matches: usize,
+    /// Number of [`EditOp::Mismatch`]es.
+    pub mismatches: usize,
+    /// Number of [`EditOp::Insert`]s. Query symbols not in target.
+    pub insertions: usize,
+    /// Number of [`EditOp::Delete`]s. Target symbols not in query.
+    pub deletions: usize,
+    /// Number of runs of insertions or deletions.
+    pub gap_opens: usize,
+    /// Length of longest run of insertions or deletions.
+    pub longest_gap: usize,
+    /// Matches over aligned length, like BLAST.
+    pub blast_identity: f64,
+    /// Matches over matches, mismatches and gap opens, so each gap counts once.
+    pub gap_compressed_identity: f64,
+    /// Fraction of query from first to last aligned pair.
+    /// * Insertions at ends of path are left out, like soft clips.
+    pub query_coverage: f64,
+    /// Fraction of target within start and end location of alignment.
+    pub target_coverage: f64,
+}
+
+/// Fraction that is `0.0` if whole is empty.
+fn fraction(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+impl Alignment {
+    /// Summary statistics of alignment path of first pair of locations.
+    /// * Fractions are `0.0` if alignment or sequence is empty.
+    ///
+    /// # Arguments
+    /// * `query_len`: Length of query.
+    /// * `target_len`: Length of target.
+    ///
+    /// # Returns
+    /// * [`AlignmentStats`], if found with [`AlignTask::Path`](crate::task::AlignTask::Path).
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, config::AlignConfig, mode::AlignMode, task::AlignTask};
+    ///
+    /// let config = AlignConfig {
+    ///     mode: AlignMode::HW,
+    ///     task: AlignTask::Path,
+    ///     ..Default::default()
+    /// };
+    /// let (query, target) = ("ACGTAACGT", "GGACGTACGTGG");
+    /// let aln = Alignment::run(config, query, target).unwrap();
+    /// let stats = aln.stats(query.len(), target.len()).unwrap();
+    /// assert_eq!((stats.matches, stats.mismatches), (8, 0));
+    /// assert_eq!((stats.insertions, stats.gap_opens, stats.longest_gap), (1, 1, 1));
+    /// assert_eq!(stats.blast_identity, 8.0 / 9.0);
+    /// assert_eq!(stats.target_coverage, 8.0 / 12.0);
+    /// ```
+    pub fn stats(&self, query_len: usize, target_len: usize) -> Option<AlignmentStats> {
+        let path = self.alignment.as_ref()?;
+        let mut stats = AlignmentStats {
+            aligned_len: path.len(),
+            ..Default::default()
+        };
+
+        let mut gap: Option<(EditOp, usize)> = None;
+        for op in path {
+            match op {
+                EditOp::Match => stats.matches += 1,
+                EditOp::Mismatch => stats.mismatches += 1,
+                EditOp::Insert => stats.insertions += 1,
+                EditOp::Delete => stats.deletions += 1,
+            }
+            gap = match (gap, op) {
+                (_, EditOp::Match | EditOp::Mismatch) => None,
+                (Some((gap_op, len)), op) if gap_op == *op => Some((gap_op, len + 1)),
+                (_, op) => {
+                    stats.gap_opens += 1;
+                    Some((*op, 1))
+                }
+            };
+            if let Some((_, len)) = gap {
+                stats.longest_gap = std::cmp::max(stats.longest_gap, len);
+            }
+        }
+
+        stats.blast_identity = fraction(stats.matches, stats.aligned_len);
+        stats.gap_compressed_identity = fraction(
+            stats.matches,
+            stats.matches + stats.mismatches + stats.gap_opens,
+        );
+        let aligned_query_len = ClippedPath::new(path).map_or(0, |clipped| {
+            clipped.aligned.len() - clipped.count(EditOp::Delete)
+        });
+        stats.query_coverage = fraction(aligned_query_len, query_len);
+        // Span of first pair of locations. End location of -1 spans no target.
+        let target_span = match (
+            self.start_locations.as_ref().and_then(|locs| locs.first()),
+            self.end_locations.as_ref().and_then(|locs| locs.first()),
+        ) {
+            (Some(start), Some(end)) => usize::try_from(end - start + 1).unwrap_or(0),
+            _ => stats.matches + stats.mismatches + stats.deletions,
+        };
+        stats.target_coverage = fraction(target_span, target_len);
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+    #[test]
+    fn test_stats_gaps() {
+        let aln = Alignment {
+            alignment: Some(vec![
+                EditOp::Match,
+                EditOp::Insert,
+                EditOp::Insert,
+                EditOp::Delete,
+                EditOp::Match,
+                EditOp::Delete,
+                EditOp::Delete,
+                EditOp::Delete,
+                EditOp::Mismatch,
+            ]),
+            start_locations: Some(vec![2]),
+            end_locations: Some(vec![8]),
+            ..Default::default()
+        };
+        let stats = aln.stats(5, 10).unwrap();
+        assert_eq!(stats.aligned_len, 9);
+        assert_eq!((stats.insertions, stats.deletions), (2, 4));
+        // Insertions followed by a deletion are two gaps.
+        assert_eq!((stats.gap_opens, stats.longest_gap), (3, 3));
+        assert_eq!(stats.gap_compressed_identity, 2.0 / 6.0);
+        assert_eq!(stats.query_coverage, 1.0);
+        assert_eq!(stats.target_coverage, 0.7);
+    }
+
+    #[test]
+    fn test_stats_query_coverage() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            task: AlignTask::Path,
+            ..Default::default()
+        };
+        // Query ends with insertions.
+        let (query, target) = ("ACGTAAAA", "CCACGTCC");
+        let aln = Alignment::run(config, query, target).unwrap();
+        let stats = aln.stats(query.len(), target.len()).unwrap();
+        assert_eq!(stats.insertions, 4);
+        assert_eq!(stats.query_coverage, 0.5);
+
+        // Only insertions.
+        let aln = Alignment {
+            alignment: Some(vec![EditOp::Insert, EditOp::Insert]),
+            ..Default::default()
+        };
+        assert_eq!(aln.stats(2, 0).unwrap().query_coverage, 0.0);
+    }
+
+    #[test]
+    fn test_stats_no_path() {
+        let aln = Alignment::run(AlignConfig::default(), "ACGT", "ACGT").unwrap();
+        assert!(aln.stats(4, 4).is_none());
+
+        let config = AlignConfig {
+            task: AlignTask::Path,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, "", "").unwrap();
+        let stats = aln.stats(0, 0).unwrap();
+        assert_eq!(stats.blast_identity, 0.0);
+        assert_eq!(stats.target_coverage, 0.0);
+    }
+}
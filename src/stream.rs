@@ -0,0 +1,311 @@
+//! Search for approximate occurrences of query within a target streamed in chunks.
+
+use std::{collections::VecDeque, io::Read};
+
+use anyhow::bail;
+
+use crate::{
+    align::{transform_query, MAX_UCHAR},
+    budget::Meter,
+    column::ColumnState,
+    config::AlignConfig,
+    dst::find_start_location,
+    equal::EqualityDefinition,
+    mode::AlignMode,
+    occurrence::Occurrence,
+    peq::QueryProfile,
+};
+
+/// Default number of bytes read at once by [`StreamSearcher::search_reader`].
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 16;
+
+/// Best end location of current run of end locations within `k`.
+struct Run {
+    end_location: usize,
+    edit_distance: usize,
+    /// Last target symbols up to and including end location.
+    window: Vec<usize>,
+}
+
+/// Searcher of all approximate occurrences of a query within a target given in chunks.
+/// * Same as [`Alignment::occurrences`](crate::align::Alignment::occurrences), but target is never fully held in memory.
+/// * Memory is bounded by query length plus `k` and chunk size.
+/// * Alphabet is fixed by query and added equalities. Other target characters never match.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{config::AlignConfig, mode::AlignMode, stream::StreamSearcher};
+///
+/// let config = AlignConfig {
+///     k: Some(1),
+///     mode: AlignMode::HW,
+///     ..Default::default()
+/// };
+/// let mut searcher = StreamSearcher::new(config, "ACGT").unwrap();
+/// let occurrences = searcher
+///     .search_chunks(["TTAC".as_bytes(), "GTTTNNAC".as_bytes(), "TTT".as_bytes()])
+///     .unwrap();
+/// assert_eq!(occurrences.len(), 2);
+/// assert_eq!(occurrences[0].start_location, 2);
+/// assert_eq!(occurrences[1].end_location, 12);
+/// ```
+pub struct StreamSearcher {
+    /// Transformed symbol of each character.
+    letter_idx: [usize; MAX_UCHAR],
+    profile: QueryProfile,
+    rev_profile: QueryProfile,
+    state: ColumnState,
+    /// Last transformed target symbols. Holds at most query length plus `k` symbols.
+    history: VecDeque<usize>,
+    /// Next target column to calculate.
+    position: usize,
+    run: Option<Run>,
+}
+
+impl StreamSearcher {
+    /// Initialize a new `StreamSearcher`.
+    ///
+    /// # Arguments
+    /// * `config`: [`AlignConfig`] configuration.
+    ///     * `k` or `k_fraction` is required.
+    ///     * `mode` must be [`AlignMode::HW`] or [`AlignMode::SHW`].
+    ///     * `task` is ignored.
+    /// * `query`: Sequence to search for.
+    pub fn new(config: AlignConfig, query: impl AsRef<str>) -> anyhow::Result<Self> {
+        if config.mode == AlignMode::NW {
+            bail!("Stream search requires AlignMode::HW or AlignMode::SHW.")
+        }
+        let (alphabet, letter_idx, transformed_query) =
+            transform_query(query.as_ref(), &config.added_equalities)?;
+        if transformed_query.is_empty() {
+            bail!("Query is empty.")
+        }
+        let Some(k) = config.threshold(transformed_query.len())? else {
+            bail!("Stream search requires k.")
+        };
+
+        let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
+        let alphabet_len = alphabet.chars().count();
+        let profile = QueryProfile::new(alphabet_len, &transformed_query, &equality_def)?;
+        let rev_transformed_query: Vec<usize> = transformed_query.iter().rev().copied().collect();
+        let rev_profile = QueryProfile::new(alphabet_len, &rev_transformed_query, &equality_def)?;
+        let state = ColumnState::new(
+            profile.max_num_blocks,
+            profile.w,
+            profile.query_len,
+            k,
+            &config.mode,
+        )?;
+
+        Ok(StreamSearcher {
+            letter_idx,
+            profile,
+            rev_profile,
+            state,
+            history: VecDeque::new(),
+            position: 0,
+            run: None,
+        })
+    }
+
+    /// Number of target characters searched so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Search next chunk of target.
+    ///
+    /// # Arguments
+    /// * `chunk`: Next ASCII characters of target.
+    ///
+    /// # Returns
+    /// * [`Occurrence`]s ending within or before chunk, with locations relative to start of target.
+    ///     * Occurrences running up to end of chunk are only returned once known to end, by later chunks or [`StreamSearcher::finish`].
+    pub fn push(&mut self, chunk: &[u8]) -> anyhow::Result<Vec<Occurrence>> {
+        let max_history = self.profile.query_len + usize::try_from(self.state.k)?;
+        let mut occurrences = vec![];
+
+        for chr in chunk {
+            let c = self.letter_idx[usize::from(*chr)];
+            if self.history.len() == max_history {
+                self.history.pop_front();
+            }
+            self.history.push_back(c);
+
+            self.state.advance(self.profile.peq_c(c))?;
+            match self.state.last_row_score() {
+                Some(score) => {
+                    if self
+                        .run
+                        .as_ref()
+                        .is_none_or(|run| score < run.edit_distance)
+                    {
+                        self.run = Some(Run {
+                            end_location: self.position,
+                            edit_distance: score,
+                            window: self.history.iter().copied().collect(),
+                        })
+                    }
+                }
+                None => {
+                    if let Some(run) = self.run.take() {
+                        occurrences.push(self.occurrence(run)?)
+                    }
+                }
+            }
+            self.position += 1;
+        }
+        Ok(occurrences)
+    }
+
+    /// Finish search at end of target.
+    ///
+    /// # Returns
+    /// * [`Occurrence`] running up to end of target, if any.
+    pub fn finish(&mut self) -> anyhow::Result<Option<Occurrence>> {
+        self.run.take().map(|run| self.occurrence(run)).transpose()
+    }
+
+    /// Search all chunks of target and finish search.
+    ///
+    /// # Arguments
+    /// * `chunks`: Consecutive chunks of target.
+    ///
+    /// # Returns
+    /// * All [`Occurrence`]s within target.
+    pub fn search_chunks<'a>(
+        &mut self,
+        chunks: impl IntoIterator<Item = &'a [u8]>,
+    ) -> anyhow::Result<Vec<Occurrence>> {
+        let mut occurrences = vec![];
+        for chunk in chunks {
+            occurrences.extend(self.push(chunk)?);
+        }
+        occurrences.extend(self.finish()?);
+        Ok(occurrences)
+    }
+
+    /// Read and search whole target in chunks of [`DEFAULT_CHUNK_SIZE`] and finish search.
+    ///
+    /// # Arguments
+    /// * `reader`: Reader of target.
+    ///
+    /// # Returns
+    /// * All [`Occurrence`]s within target.
+    pub fn search_reader(&mut self, mut reader: impl Read) -> anyhow::Result<Vec<Occurrence>> {
+        let mut occurrences = vec![];
+        let mut chunk = vec![0; DEFAULT_CHUNK_SIZE];
+        loop {
+            let num_read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(num_read) => num_read,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            occurrences.extend(self.push(&chunk[..num_read])?);
+        }
+        occurrences.extend(self.finish()?);
+        Ok(occurrences)
+    }
+
+    fn occurrence(&self, run: Run) -> anyhow::Result<Occurrence> {
+        let window_end = run.window.len() - 1;
+        let start_location = find_start_location(
+            &self.rev_profile.peq,
+            self.rev_profile.w,
+            self.rev_profile.max_num_blocks,
+            self.rev_profile.query_len,
+            &run.window,
+            window_end,
+            run.edit_distance,
+            &mut Meter::default(),
+        )?;
+        Ok(Occurrence {
+            start_location: run.end_location - window_end + usize::try_from(start_location)?,
+            end_location: run.end_location,
+            edit_distance: run.edit_distance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{align::Alignment, equal::EqualityPair};
+
+    fn hw_config(k: usize) -> AlignConfig {
+        AlignConfig {
+            k: Some(k),
+            mode: AlignMode::HW,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stream_matches_occurrences() {
+        let query = "GATTACAGATTACA";
+        let target = str::repeat("TTGATTACAGATAACATTTTGATTCAGATTACACCC", 20);
+        let exp_occurrences: Vec<Occurrence> = Alignment::occurrences(hw_config(2), query, &target)
+            .unwrap()
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        assert_eq!(exp_occurrences.len(), 40);
+
+        for chunk_size in [1, 7, 64, 1000] {
+            let mut searcher = StreamSearcher::new(hw_config(2), query).unwrap();
+            let occurrences = searcher
+                .search_chunks(target.as_bytes().chunks(chunk_size))
+                .unwrap();
+            assert_eq!(occurrences, exp_occurrences);
+            assert_eq!(searcher.position(), target.len());
+        }
+
+        let mut searcher = StreamSearcher::new(hw_config(2), query).unwrap();
+        let occurrences = searcher.search_reader(Cursor::new(&target)).unwrap();
+        assert_eq!(occurrences, exp_occurrences);
+    }
+
+    #[test]
+    fn test_stream_added_equalities() {
+        let config = AlignConfig {
+            added_equalities: vec![EqualityPair {
+                first: 'A',
+                second: 'N',
+            }],
+            ..hw_config(0)
+        };
+        let mut searcher = StreamSearcher::new(config, "ACGT").unwrap();
+        let occurrences = searcher.search_chunks(["XXNCG".as_bytes(), "TXX".as_bytes()]);
+        assert_eq!(
+            occurrences.unwrap(),
+            [Occurrence {
+                start_location: 2,
+                end_location: 5,
+                edit_distance: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stream_k_fraction() {
+        let config = AlignConfig {
+            k: None,
+            k_fraction: Some(0.25),
+            ..hw_config(0)
+        };
+        let mut searcher = StreamSearcher::new(config, "ACGT").unwrap();
+        let occurrences = searcher.search_chunks(["TTAGGT".as_bytes()]).unwrap();
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].edit_distance, 1);
+        assert!(StreamSearcher::new(
+            AlignConfig {
+                k: None,
+                ..hw_config(0)
+            },
+            "ACGT"
+        )
+        .is_err());
+    }
+}
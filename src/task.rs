@@ -1,3 +1,5 @@
+//! Alignment tasks.
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 /// What should rs_edlib do?
 pub enum AlignTask {
@@ -0,0 +1,130 @@
+//! Best alignments of one query against many targets.
+
+use crate::{align::Alignment, config::AlignConfig};
+
+impl Alignment {
+    /// Aligns query to each target, returning the `n` best [`Alignment`]s.
+    /// * `k` of each alignment is lowered to one less than the `n`-th best edit distance so far, so worse targets are given up early.
+    /// * Once `n` targets with edit distance `0` are found, the remaining targets are skipped.
+    ///
+    /// * @param `config`: [`AlignConfig`] configuration.
+    ///     * `k` and `k_fraction` limit edit distance of all targets. If `None`, unlimited until `n` targets are found.
+    /// * @param `query`: First sequence.
+    /// * @param `targets`: Second sequences.
+    /// * @param `n`: Number of targets to return.
+    ///
+    /// # Returns
+    /// * Up to `n` pairs of target index and [`Alignment`], sorted by edit distance and then target index.
+    ///     * Of targets with equal edit distance, the first ones are kept.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, config::AlignConfig};
+    ///
+    /// let targets = ["TTTTTTTT", "ACGTACGA", "ACGTACGT", "ACGAACGA"];
+    /// let best = Alignment::top_n(AlignConfig::default(), "ACGTACGT", targets, 2).unwrap();
+    /// let indices: Vec<usize> = best.iter().map(|(idx, _)| *idx).collect();
+    /// assert_eq!(indices, [2, 1]);
+    /// assert_eq!(best[1].1.edit_distance, Some(1));
+    /// ```
+    pub fn top_n(
+        config: AlignConfig,
+        query: impl AsRef<str>,
+        targets: impl IntoIterator<Item = impl AsRef<str>>,
+        n: usize,
+    ) -> anyhow::Result<Vec<(usize, Alignment)>> {
+        let query = query.as_ref();
+        let threshold = config.threshold(query.chars().count())?;
+        // Sorted by edit distance and then target index.
+        let mut best: Vec<(usize, usize, Alignment)> = Vec::with_capacity(n + 1);
+        if n == 0 {
+            return Ok(vec![]);
+        }
+
+        for (target_idx, target) in targets.into_iter().enumerate() {
+            let k = match best.get(n - 1) {
+                // Only strictly better targets can replace n-th best.
+                Some((0, _, _)) => break,
+                Some((worst_distance, _, _)) => Some(worst_distance - 1),
+                None => None,
+            };
+            let k = match (k, threshold) {
+                (Some(k), Some(threshold)) => Some(std::cmp::min(k, threshold)),
+                (k, threshold) => k.or(threshold),
+            };
+
+            let alignment = Alignment::run(
+                AlignConfig {
+                    k,
+                    ..config.clone()
+                },
+                query,
+                target,
+            )?;
+            // Distance of empty sequences is not limited by k.
+            let Some(edit_distance) = alignment
+                .edit_distance
+                .filter(|edit_distance| k.is_none_or(|k| *edit_distance <= k))
+            else {
+                continue;
+            };
+
+            let pos = best.partition_point(|(distance, _, _)| *distance <= edit_distance);
+            best.insert(pos, (edit_distance, target_idx, alignment));
+            best.truncate(n);
+        }
+        Ok(best
+            .into_iter()
+            .map(|(_, target_idx, alignment)| (target_idx, alignment))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mode::AlignMode;
+
+    #[test]
+    fn test_top_n_hw() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            ..Default::default()
+        };
+        let targets = ["GGGGACGGGG", "TTACGTTT", "ACCT", "TACGAT", "ACGT"];
+        let best = Alignment::top_n(config, "ACGT", targets, 3).unwrap();
+        let summary: Vec<(usize, Option<usize>)> = best
+            .iter()
+            .map(|(target_idx, alignment)| (*target_idx, alignment.edit_distance))
+            .collect();
+        assert_eq!(summary, [(1, Some(0)), (4, Some(0)), (0, Some(1))]);
+    }
+
+    #[test]
+    fn test_top_n_k() {
+        let config = AlignConfig {
+            k: Some(1),
+            ..Default::default()
+        };
+        let best = Alignment::top_n(config, "ACGT", ["", "ACGA", "TTTT", "ACG"], 10).unwrap();
+        let indices: Vec<usize> = best.iter().map(|(target_idx, _)| *target_idx).collect();
+        assert_eq!(indices, [1, 3]);
+        assert!(
+            Alignment::top_n(AlignConfig::default(), "ACGT", ["ACGT"], 0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_top_n_k_fraction() {
+        let config = AlignConfig {
+            k_fraction: Some(0.34),
+            ..Default::default()
+        };
+        // k is a fraction of characters of query, not bytes.
+        let best = Alignment::top_n(config, "ééé", ["ééé", "aéa", "éé", "a"], 10).unwrap();
+        let indices: Vec<usize> = best.iter().map(|(target_idx, _)| *target_idx).collect();
+        assert_eq!(indices, [0, 2]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Record of the banded dynamic programming matrix for debugging and teaching.
+
+use std::io::Write;
+
+use crate::{align::WORD_SIZE, column::ColumnState};
+
+/// Band and cells of one calculated target column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceColumn {
+    /// Zero-based index of target column.
+    pub column: usize,
+    /// Threshold number of differences when column was calculated.
+    pub k: usize,
+    /// 0-based indices of first and last block of Ukkonen band after column. `None` if band stopped existing.
+    pub band: Option<(usize, usize)>,
+    /// Number of blocks in band.
+    pub num_blocks: usize,
+    /// Scores of cells within band from top to bottom, starting at first row of first block.
+    /// * Padded rows after end of query are omitted.
+    pub cells: Vec<isize>,
+}
+
+impl TraceColumn {
+    /// Score of query row, if within band.
+    pub fn cell(&self, row: usize) -> Option<isize> {
+        let (first_block_idx, _) = self.band?;
+        let first_row = first_block_idx * WORD_SIZE as usize;
+        self.cells.get(row.checked_sub(first_row)?).copied()
+    }
+}
+
+/// Record of every calculated column of [`Alignment::run`](crate::align::Alignment::run).
+/// * Only recorded if [`AlignConfig::trace`](crate::config::AlignConfig::trace) is set. Memory is quadratic.
+/// * Each retry with a larger `k` is a separate pass. Search of start locations is not recorded.
+///
+/// ### Example
+/// ```
+/// use rs_edlib::{align::Alignment, config::AlignConfig};
+///
+/// let config = AlignConfig {
+///     k: Some(1),
+///     trace: true,
+///     ..Default::default()
+/// };
+/// let aln = Alignment::run(config, "ACGT", "AGT").unwrap();
+/// let trace = aln.trace.unwrap();
+/// assert_eq!(trace.passes[0][0].cells, [0, 1, 2, 3]);
+/// assert_eq!(
+///     trace.picture(),
+///     "pass 0\n\
+///      01+\n\
+///      11+\n\
+///      +1+\n\
+///      ++1\n\
+///      ---\n\
+///      111\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// Length of query. Number of rows.
+    pub query_len: usize,
+    /// Columns of each pass.
+    pub passes: Vec<Vec<TraceColumn>>,
+}
+
+impl Trace {
+    /// Initialize an empty `Trace`.
+    pub(crate) fn new(query_len: usize) -> Self {
+        Trace {
+            query_len,
+            passes: vec![],
+        }
+    }
+
+    /// Record column. Column `0` starts a new pass.
+    pub(crate) fn record(&mut self, column: usize, state: &ColumnState) {
+        if column == 0 || self.passes.is_empty() {
+            self.passes.push(vec![]);
+        }
+        let band = (!state.is_empty()).then_some((
+            state.first_block_idx as usize,
+            state.last_block_idx as usize,
+        ));
+        let cells = band.map_or(vec![], |(first_block_idx, last_block_idx)| {
+            let first_row = first_block_idx * WORD_SIZE as usize;
+            state.blocks[first_block_idx..=last_block_idx]
+                .iter()
+                // Cells of block are from bottom to top.
+                .flat_map(|block| block.get_cell_values().into_iter().rev())
+                .take(self.query_len.saturating_sub(first_row))
+                .collect()
+        });
+        let pass = self.passes.last_mut().expect("No pass.");
+        pass.push(TraceColumn {
+            column,
+            k: state.k as usize,
+            band,
+            num_blocks: state.num_band_blocks(),
+            cells,
+        })
+    }
+
+    /// Write all columns as CSV with a header.
+    /// * Columns are `pass,column,k,first_block_idx,last_block_idx,num_blocks,cells`.
+    /// * Cells are separated by spaces. Block indices are empty if band stopped existing.
+    ///
+    /// # Arguments
+    /// * `writer`: Writer of CSV.
+    pub fn write_csv(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        writeln!(
+            writer,
+            "pass,column,k,first_block_idx,last_block_idx,num_blocks,cells"
+        )?;
+        for (pass_idx, pass) in self.passes.iter().enumerate() {
+            for column in pass {
+                let (first_block_idx, last_block_idx) = column.band.map_or(
+                    (String::new(), String::new()),
+                    |(first_block_idx, last_block_idx)| {
+                        (first_block_idx.to_string(), last_block_idx.to_string())
+                    },
+                );
+                let cells: Vec<String> = column.cells.iter().map(|cell| cell.to_string()).collect();
+                writeln!(
+                    writer,
+                    "{pass_idx},{},{},{first_block_idx},{last_block_idx},{},{}",
+                    column.column,
+                    column.k,
+                    column.num_blocks,
+                    cells.join(" ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Text picture of band over dynamic programming matrix of each pass.
+    /// * Rows are query and columns are target.
+    /// * Cells within `k` are shown as a base 36 digit, or `#` if larger than `35`.
+    /// * Cells within band but larger than `k` are shown as `+`. Cells outside of band are shown as `.`.
+    /// * Below a line of `-`, the last row shows `k` of each column, as it can be lowered within a pass.
+    pub fn picture(&self) -> String {
+        let digit = |value: usize| char::from_digit(value as u32, 36).unwrap_or('#');
+        let mut picture = String::new();
+        for (pass_idx, pass) in self.passes.iter().enumerate() {
+            picture.push_str(&format!("pass {pass_idx}\n"));
+            for row in 0..self.query_len {
+                picture.extend(pass.iter().map(|column| match column.cell(row) {
+                    None => '.',
+                    Some(score) if score > column.k as isize => '+',
+                    Some(score) => digit(score as usize),
+                }));
+                picture.push('\n');
+            }
+            picture.push_str(&"-".repeat(pass.len()));
+            picture.push('\n');
+            picture.extend(pass.iter().map(|column| digit(column.k)));
+            picture.push('\n');
+        }
+        picture
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{align::Alignment, config::AlignConfig, mode::AlignMode};
+
+    #[test]
+    fn test_trace_csv() {
+        let config = AlignConfig {
+            k: Some(1),
+            mode: AlignMode::HW,
+            trace: true,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, "ACG", "TACGT").unwrap();
+        let trace = aln.trace.unwrap();
+        assert_eq!(trace.passes.len(), 1);
+        assert_eq!(trace.passes[0].len(), 5);
+
+        let mut csv = vec![];
+        trace.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1], "0,0,1,0,0,1,1 2 3");
+        assert_eq!(lines[4], "0,3,1,0,0,1,1 1 0");
+        // Best score lowers k.
+        assert_eq!(lines[5], "0,4,0,0,0,1,1 2 1");
+    }
+
+    #[test]
+    fn test_trace_picture_k() {
+        let config = AlignConfig {
+            k: Some(1),
+            mode: AlignMode::HW,
+            trace: true,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, "ACG", "TACGT").unwrap();
+        // Best score lowers k of last column.
+        assert_eq!(
+            aln.trace.unwrap().picture(),
+            "pass 0\n\
+             1011+\n\
+             +101+\n\
+             ++10+\n\
+             -----\n\
+             11110\n"
+        );
+    }
+
+    #[test]
+    fn test_trace_long_query() {
+        let query = str::repeat("ACGT", 40);
+        let config = AlignConfig {
+            k: Some(10),
+            trace: true,
+            ..Default::default()
+        };
+        let aln = Alignment::run(config, &query, &query).unwrap();
+        let trace = aln.trace.unwrap();
+        let last = trace.passes[0].last().unwrap();
+        // Band is bounded by diagonal through last cell.
+        assert_eq!(last.band, Some((2, 2)));
+        // Padding of last block is omitted.
+        assert_eq!(last.cells.len(), 160 - 128);
+        assert_eq!(last.cell(159), Some(0));
+        assert_eq!(last.cell(0), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Score of best alignment ending at each target column.
+
+use anyhow::bail;
+
+use crate::{
+    align::{transform_sequences, Alignment},
+    column::ColumnState,
+    config::AlignConfig,
+    equal::EqualityDefinition,
+    mode::AlignMode,
+    peq::QueryProfile,
+};
+
+/// Lazy iterator over the score track of a semi-global search.
+/// * Yields edit distance of best alignment of query ending at each target column.
+/// * Scores larger than `k` are not calculated exactly, so they are `None`.
+pub struct ScoreTrack {
+    pub(crate) profile: QueryProfile,
+    pub(crate) target: Vec<usize>,
+    pub(crate) state: ColumnState,
+    /// Next target column to calculate.
+    pub(crate) position: usize,
+}
+
+impl Alignment {
+    /// Calculates score track of query over target, returning a [`ScoreTrack`] iterator.
+    ///
+    /// * @param `config`: [`AlignConfig`] configuration.
+    ///     * `k` and `k_fraction` cap scores. If neither is set, query length is used.
+    ///     * `mode` must be [`AlignMode::HW`] or [`AlignMode::SHW`].
+    ///     * `task` is ignored.
+    /// * @param `query`: First sequence.
+    /// * @param `target`: Second sequence.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{align::Alignment, config::AlignConfig, mode::AlignMode};
+    ///
+    /// let config = AlignConfig {
+    ///     k: Some(2),
+    ///     mode: AlignMode::HW,
+    ///     ..Default::default()
+    /// };
+    /// let track = Alignment::score_track(config, "ACGT", "TTACGTTT")
+    ///     .unwrap()
+    ///     .collect::<anyhow::Result<Vec<Option<u32>>>>()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     track,
+    ///     [None, None, None, Some(2), Some(1), Some(0), Some(1), Some(2)]
+    /// );
+    /// ```
+    pub fn score_track(
+        config: AlignConfig,
+        query: impl AsRef<str>,
+        target: impl AsRef<str>,
+    ) -> anyhow::Result<ScoreTrack> {
+        if config.mode == AlignMode::NW {
+            bail!("Score track requires AlignMode::HW or AlignMode::SHW.")
+        }
+        let (alphabet, transformed_query, transformed_target) =
+            transform_sequences(query.as_ref(), target.as_ref());
+        if transformed_query.is_empty() {
+            bail!("Query is empty.")
+        }
+        let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
+        let alphabet_len = alphabet.chars().count();
+        let profile = QueryProfile::new(alphabet_len, &transformed_query, &equality_def)?;
+
+        ScoreTrack::new(
+            profile,
+            transformed_target,
+            config
+                .threshold(transformed_query.len())?
+                .unwrap_or(transformed_query.len()),
+            &config.mode,
+        )
+    }
+}
+
+impl ScoreTrack {
+    pub(crate) fn new(
+        profile: QueryProfile,
+        target: Vec<usize>,
+        k: usize,
+        mode: &AlignMode,
+    ) -> anyhow::Result<Self> {
+        let state = ColumnState::new(
+            profile.max_num_blocks,
+            profile.w,
+            profile.query_len,
+            k,
+            mode,
+        )?;
+        Ok(ScoreTrack {
+            profile,
+            target,
+            state,
+            position: 0,
+        })
+    }
+
+    /// Calculate next target column.
+    ///
+    /// # Returns
+    /// * `None` if at end of target.
+    /// * Score of column if not larger than `k`.
+    pub(crate) fn next_score(&mut self) -> Option<anyhow::Result<Option<usize>>> {
+        let c = *self.target.get(self.position)?;
+        self.position += 1;
+        Some(
+            self.state
+                .advance(self.profile.peq_c(c))
+                .map(|_| self.state.last_row_score()),
+        )
+    }
+
+    /// Threshold of scores.
+    pub fn k(&self) -> usize {
+        self.state.k as usize
+    }
+}
+
+impl Iterator for ScoreTrack {
+    /// Score of next column. `None` if larger than `k`.
+    type Item = anyhow::Result<Option<u32>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(
+            self.next_score()?
+                .and_then(|score| score.map(|score| Ok(u32::try_from(score)?)).transpose()),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.target.len() - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for ScoreTrack {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_score_track_shw() {
+        let config = AlignConfig {
+            k: Some(3),
+            mode: AlignMode::SHW,
+            ..Default::default()
+        };
+        let track = Alignment::score_track(config, "ACGT", "ACGTTTTT")
+            .unwrap()
+            .collect::<anyhow::Result<Vec<Option<u32>>>>()
+            .unwrap();
+        // Gaps before query are penalized. Scores are never larger than k.
+        assert_eq!(
+            track,
+            [
+                Some(3),
+                Some(2),
+                Some(1),
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_score_track_default_k() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            ..Default::default()
+        };
+        let track = Alignment::score_track(config, "ACGT", "GGGG").unwrap();
+        assert_eq!(track.k(), 4);
+        assert_eq!(
+            track.collect::<anyhow::Result<Vec<Option<u32>>>>().unwrap(),
+            [Some(3); 4]
+        );
+    }
+
+    #[test]
+    fn test_score_track_k_fraction() {
+        let config = AlignConfig {
+            mode: AlignMode::HW,
+            k_fraction: Some(0.5),
+            ..Default::default()
+        };
+        let track = Alignment::score_track(config, "ACGT", "GGGG").unwrap();
+        assert_eq!(track.k(), 2);
+        assert_eq!(
+            track.collect::<anyhow::Result<Vec<Option<u32>>>>().unwrap(),
+            [None; 4]
+        );
+    }
+}
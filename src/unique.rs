@@ -0,0 +1,174 @@
+//! Uniqueness of best alignment of query within target.
+
+use std::collections::VecDeque;
+
+use crate::{align::Alignment, budget::Meter, track::ScoreTrack};
+
+/// Maximum confidence of [`Uniqueness`]. Same as the maximum mapping quality reported by common read mappers.
+pub const MAX_CONFIDENCE: u8 = 60;
+
+/// Best and second-best distinct hit of query within target.
+/// * Hits are distinct if their end locations are at least query length apart, so they cannot overlap without gaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uniqueness {
+    /// Edit distance of best hit.
+    pub best_distance: usize,
+    /// Edit distance of best hit not overlapping the best hit.
+    /// * `None` if no other hit has an edit distance not larger than `k`.
+    pub second_best_distance: Option<usize>,
+    /// Confidence that best hit is the true location of query, similar to MAPQ.
+    /// * `0` if best hit is not unique and [`MAX_CONFIDENCE`] if there is no second-best hit.
+    /// * Otherwise, `MAX_CONFIDENCE * (1 - best / second_best)`.
+    pub confidence: u8,
+}
+
+impl Uniqueness {
+    /// Initialize a new `Uniqueness` and calculate its confidence.
+    ///
+    /// # Arguments
+    /// * `best_distance`: Edit distance of best hit.
+    /// * `second_best_distance`: Edit distance of second-best distinct hit.
+    ///
+    /// # Examples
+    /// ```
+    /// use rs_edlib::unique::Uniqueness;
+    ///
+    /// assert_eq!(Uniqueness::new(1, Some(1)).confidence, 0);
+    /// assert_eq!(Uniqueness::new(1, Some(4)).confidence, 45);
+    /// assert_eq!(Uniqueness::new(1, None).confidence, 60);
+    /// ```
+    pub fn new(best_distance: usize, second_best_distance: Option<usize>) -> Self {
+        let confidence = match second_best_distance {
+            Some(second_best_distance) => {
+                let diff = second_best_distance.saturating_sub(best_distance);
+                // Second best can only be 0 if best is also 0.
+                (usize::from(MAX_CONFIDENCE) * diff)
+                    .checked_div(second_best_distance)
+                    .unwrap_or(0) as u8
+            }
+            None => MAX_CONFIDENCE,
+        };
+        Uniqueness {
+            best_distance,
+            second_best_distance,
+            confidence,
+        }
+    }
+}
+
+impl Alignment {
+    /// Find best and second-best distinct hit in a single pass of a semi-global search.
+    /// * Sets edit distance and end locations of best hit as well as [`Uniqueness`].
+    /// * `k` of score track is never lowered, so second-best hit is found with edit distance up to `k`.
+    pub(crate) fn calc_uniqueness(
+        &mut self,
+        mut track: ScoreTrack,
+        meter: &mut Meter,
+    ) -> anyhow::Result<()> {
+        // Minimum end location distance between distinct hits.
+        let window = track.profile.query_len;
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut positions: Vec<isize> = vec![];
+        let mut second_best: Option<usize> = None;
+        // Scores of last `window` columns and min of all columns before them.
+        let mut recent_scores: VecDeque<Option<usize>> = VecDeque::with_capacity(window + 1);
+        let mut far_min: Option<usize> = None;
+
+        let mut c = 0;
+        while let Some(score) = track.next_score() {
+            let score = score?;
+            meter.charge(track.state.num_band_blocks())?;
+            meter.report(c, &track.state, || {
+                min_score(best.map(|(best_score, _)| best_score), score)
+            });
+            meter.record(c, &track.state);
+            recent_scores.push_back(score);
+            if recent_scores.len() > window {
+                far_min = min_score(far_min, recent_scores.pop_front().flatten());
+            }
+
+            if let Some(score) = score {
+                match best {
+                    Some((best_score, best_end)) if score >= best_score => {
+                        if score == best_score {
+                            positions.push(c.try_into()?);
+                        }
+                        if c >= best_end + window {
+                            second_best = min_score(second_best, Some(score));
+                        }
+                    }
+                    // New best. Only columns far enough before it are distinct.
+                    _ => {
+                        best = Some((score, c));
+                        positions = vec![c.try_into()?];
+                        second_best = far_min;
+                    }
+                }
+            }
+            c += 1;
+        }
+
+        self.edit_distance = best.map(|(best_score, _)| best_score);
+        if let Some((best_score, _)) = best {
+            self.end_locations = Some(positions);
+            self.uniqueness = Some(Uniqueness::new(best_score, second_best));
+        }
+        Ok(())
+    }
+}
+
+fn min_score(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{config::AlignConfig, mode::AlignMode, task::AlignTask};
+
+    use super::*;
+
+    fn config(k: Option<usize>) -> AlignConfig {
+        AlignConfig {
+            k,
+            mode: AlignMode::HW,
+            task: AlignTask::Loc,
+            uniqueness: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_uniqueness() {
+        let aln = Alignment::run(config(Some(2)), "ACGTAC", "TTTACGTACTTTTTACGAACTTTT").unwrap();
+        assert_eq!(aln.edit_distance, Some(0));
+        assert_eq!(aln.start_locations, Some(vec![3]));
+        assert_eq!(aln.end_locations, Some(vec![8]));
+        assert_eq!(aln.uniqueness, Some(Uniqueness::new(0, Some(1))));
+        assert_eq!(aln.uniqueness.unwrap().confidence, MAX_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_uniqueness_repeat() {
+        let aln = Alignment::run(config(Some(2)), "ACGTAC", "ACGTACTTTTTTTACGTACTTT").unwrap();
+        assert_eq!(aln.end_locations, Some(vec![5, 18]));
+        let uniqueness = aln.uniqueness.unwrap();
+        assert_eq!(uniqueness.second_best_distance, Some(0));
+        assert_eq!(uniqueness.confidence, 0);
+    }
+
+    #[test]
+    fn test_uniqueness_no_second_best() {
+        let aln = Alignment::run(config(Some(0)), "ACGTAC", "TTTACGTACTTTTTACGAACTTTT").unwrap();
+        assert_eq!(aln.uniqueness, Some(Uniqueness::new(0, None)));
+    }
+
+    #[test]
+    fn test_uniqueness_requires_k() {
+        assert!(Alignment::run(config(None), "ACGTAC", "TTTACGTACTTTTTACGAACTTTT").is_err());
+        assert!(Alignment::run(config(None), "", "ACGT").is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! Small variants of a query against a target and their VCF output.
+//! * See https://samtools.github.io/hts-specs/VCFv4.2.pdf
+
+use std::{fmt::Display, io::Write};
+
+use anyhow::bail;
+
+use crate::{align::Alignment, cigar::EditOp, normalize::left_normalize};
+
+/// Kind of a [`Variant`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariantKind {
+    /// Single nucleotide variant.
+    Snv,
+    /// Multi nucleotide variant. Substitution of several symbols, like a run of adjacent mismatches.
+    Mnv,
+    /// Query has symbols that target does not.
+    Insertion,
+    /// Target has symbols that query does not.
+    Deletion,
+    /// Run of mismatches next to insertions or deletions.
+    Complex,
+}
+
+impl Display for VariantKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantKind::Snv => write!(f, "SNV"),
+            VariantKind::Mnv => write!(f, "MNV"),
+            VariantKind::Insertion => write!(f, "INS"),
+            VariantKind::Deletion => write!(f, "DEL"),
+            VariantKind::Complex => write!(f, "COMPLEX"),
+        }
+    }
+}
+
+/// Difference of query from target, anchored like in VCF.
+/// * Target is reference and query is alternate.
+/// * Insertions and deletions include an unchanged anchor symbol of target before them,
+///   or after them if they are at start of target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    /// Zero-based position in target of first symbol of `reference`.
+    pub pos: usize,
+    /// Symbols of target.
+    pub reference: String,
+    /// Symbols of query in place of `reference`.
+    pub alternate: String,
+}
+
+impl Variant {
+    /// [`VariantKind`] by lengths of reference and alternate, and if the shorter one is an anchor of the longer one.
+    pub fn kind(&self) -> VariantKind {
+        let (ref_len, alt_len) = (
+            self.reference.chars().count(),
+            self.alternate.chars().count(),
+        );
+        let is_anchor =
+            |anchor: &str, indel: &str| indel.starts_with(anchor) || indel.ends_with(anchor);
+        match ref_len.cmp(&alt_len) {
+            std::cmp::Ordering::Less if is_anchor(&self.reference, &self.alternate) => {
+                VariantKind::Insertion
+            }
+            std::cmp::Ordering::Greater if is_anchor(&self.alternate, &self.reference) => {
+                VariantKind::Deletion
+            }
+            std::cmp::Ordering::Equal if ref_len == 1 => VariantKind::Snv,
+            std::cmp::Ordering::Equal => VariantKind::Mnv,
+            _ => VariantKind::Complex,
+        }
+    }
+}
+
+/// Columns of an alignment path making up one [`Variant`].
+#[derive(Debug, Clone, Copy)]
+struct VariantSpan {
+    /// First column.
+    start: usize,
+    /// Column after last one.
+    end: usize,
+    /// Anchor symbol of target before alignment.
+    anchor_before: Option<char>,
+    /// Anchor symbol of target after alignment.
+    anchor_after: Option<char>,
+}
+
+/// Variants of query along an alignment path.
+/// * Indels are left-normalized first, see [`left_normalize`].
+/// * Each run of adjacent mismatches and indels is one [`Variant`], so variants never overlap.
+///   Runs sharing an anchor symbol are merged.
+///
+/// # Arguments
+/// * `query`: Aligned query.
+/// * `target`: Whole target.
+/// * `path`: [`EditOp`]s from start to end of alignment.
+/// * `target_start`: Zero-based position in target where alignment starts.
+///
+/// # Returns
+/// * [`Variant`]s by position. Error if path does not fit sequences, or an indel has no anchor symbol,
+///   like a deletion of whole target.
+pub fn call_variants(
+    query: &str,
+    target: &str,
+    path: &[EditOp],
+    target_start: usize,
+) -> anyhow::Result<Vec<Variant>> {
+    let target: Vec<char> = target.chars().collect();
+    let aligned_target_len = path.iter().filter(|op| **op != EditOp::Insert).count();
+    let target_end = target_start + aligned_target_len;
+    if target_end > target.len() {
+        bail!(
+            "Path spans target up to {target_end}, beyond its length {}.",
+            target.len()
+        )
+    }
+    let aligned_target: String = target[target_start..target_end].iter().collect();
+    let path = left_normalize(query, &aligned_target, path)?;
+    let query: Vec<char> = query.chars().collect();
+
+    let mut spans: Vec<VariantSpan> = vec![];
+    let mut idx = 0;
+    while idx < path.len() {
+        if path[idx] == EditOp::Match {
+            idx += 1;
+            continue;
+        }
+        let run_end = idx
+            + path[idx..]
+                .iter()
+                .take_while(|op| **op != EditOp::Match)
+                .count();
+        let mut span = VariantSpan {
+            start: idx,
+            end: run_end,
+            anchor_before: None,
+            anchor_after: None,
+        };
+        // Insertions or deletions alone need an anchor symbol, as reference or alternate would be empty.
+        let run = &path[idx..run_end];
+        if run.iter().all(|op| *op == EditOp::Insert) || run.iter().all(|op| *op == EditOp::Delete)
+        {
+            if span.start > 0 {
+                span.start -= 1;
+            } else if target_start > 0 {
+                span.anchor_before = Some(target[target_start - 1]);
+            } else if span.end < path.len() {
+                span.end += 1;
+            } else if target_end < target.len() {
+                span.anchor_after = Some(target[target_end]);
+            } else {
+                bail!("No anchor symbol for indel at target position {target_start}.")
+            }
+        }
+        match spans.last_mut() {
+            // Anchor is shared with previous variant.
+            Some(prev) if prev.end > span.start => {
+                prev.end = span.end;
+                prev.anchor_after = span.anchor_after;
+            }
+            _ => spans.push(span),
+        }
+        idx = run_end;
+    }
+
+    // Query and target positions before each column.
+    let mut positions = Vec::with_capacity(path.len() + 1);
+    let (mut query_pos, mut target_pos) = (0, target_start);
+    positions.push((query_pos, target_pos));
+    for op in path.iter() {
+        if *op != EditOp::Delete {
+            query_pos += 1;
+        }
+        if *op != EditOp::Insert {
+            target_pos += 1;
+        }
+        positions.push((query_pos, target_pos));
+    }
+
+    let mut variants = Vec::with_capacity(spans.len());
+    for span in spans {
+        let (query_start, target_start) = positions[span.start];
+        let (query_end, target_end) = positions[span.end];
+        let (before, after) = (span.anchor_before.iter(), span.anchor_after.iter());
+        let variant = Variant {
+            pos: target_start - before.len(),
+            reference: before
+                .clone()
+                .chain(target[target_start..target_end].iter())
+                .chain(after.clone())
+                .collect(),
+            alternate: before
+                .chain(query[query_start..query_end].iter())
+                .chain(after)
+                .collect(),
+        };
+        // Insertion next to deletion of same symbols is no variant.
+        if variant.reference != variant.alternate {
+            variants.push(variant);
+        }
+    }
+    Ok(variants)
+}
+
+/// Write a minimal VCF 4.2 file of variants of one target.
+/// * `INFO` has `TYPE` of [`VariantKind`]. `ID`, `QUAL` and samples are left out.
+///
+/// # Arguments
+/// * `writer`: Writer of VCF.
+/// * `target_name`: Name of target, as `CHROM`.
+/// * `target_len`: Length of target.
+/// * `variants`: [`Variant`]s by position.
+pub fn write_vcf(
+    mut writer: impl Write,
+    target_name: &str,
+    target_len: usize,
+    variants: &[Variant],
+) -> anyhow::Result<()> {
+    writeln!(writer, "##fileformat=VCFv4.2")?;
+    writeln!(writer, "##source={}", env!("CARGO_PKG_NAME"))?;
+    writeln!(writer, "##contig=<ID={target_name},length={target_len}>")?;
+    writeln!(
+        writer,
+        "##INFO=<ID=TYPE,Number=1,Type=String,Description=\"Type of variant: SNV, MNV, INS, DEL or COMPLEX\">"
+    )?;
+    writeln!(writer, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+    for variant in variants {
+        writeln!(
+            writer,
+            "{target_name}\t{}\t.\t{}\t{}\t.\tPASS\tTYPE={}",
+            variant.pos + 1,
+            variant.reference,
+            variant.alternate,
+            variant.kind()
+        )?;
+    }
+    Ok(())
+}
+
+impl Alignment {
+    /// [`Variant`]s of query along alignment path of first pair of locations. See [`call_variants`].
+    ///
+    /// # Arguments
+    /// * `query`: Query of alignment.
+    /// * `target`: Whole target of alignment.
+    ///
+    /// ### Example
+    /// ```
+    /// use rs_edlib::{
+    ///     align::Alignment,
+    ///     config::AlignConfig,
+    ///     task::AlignTask,
+    ///     variant::VariantKind,
+    /// };
+    ///
+    /// let config = AlignConfig {
+    ///     task: AlignTask::Path,
+    ///     ..Default::default()
+    /// };
+    /// let (query, target) = ("GATTTTACC", "GATTTACA");
+    /// let aln = Alignment::run(config, query, target).unwrap();
+    /// let variants = aln.variants(query, target).unwrap();
+    /// assert_eq!(variants.len(), 2);
+    /// // Insertion of T at start of homopolymer, anchored to A before it.
+    /// assert_eq!(variants[0].pos, 1);
+    /// assert_eq!((variants[0].reference.as_str(), variants[0].alternate.as_str()), ("A", "AT"));
+    /// assert_eq!(variants[1].kind(), VariantKind::Snv);
+    /// ```
+    pub fn variants(&self, query: &str, target: &str) -> anyhow::Result<Vec<Variant>> {
+        let (Some(path), Some(start)) = (
+            self.alignment.as_ref(),
+            self.start_locations.as_ref().and_then(|locs| locs.first()),
+        ) else {
+            bail!("No alignment path to call variants of.")
+        };
+        call_variants(query, target, path, usize::try_from(*start)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cigar::Cigar;
+
+    fn variants(query: &str, target: &str, cigar: &str, target_start: usize) -> Vec<String> {
+        let path = cigar.parse::<Cigar>().unwrap().to_path().unwrap();
+        call_variants(query, target, &path, target_start)
+            .unwrap()
+            .iter()
+            .map(|variant| {
+                format!(
+                    "{}:{}>{}:{}",
+                    variant.pos,
+                    variant.reference,
+                    variant.alternate,
+                    variant.kind()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_call_variants() {
+        assert_eq!(
+            variants("ACGTTGCA", "ACCATGGA", "2=2X2=1X1=", 0),
+            ["2:CA>GT:MNV", "6:G>C:SNV"]
+        );
+        // Deletion of CA in tandem repeat is left-normalized.
+        assert_eq!(variants("TCACAG", "TCACACAG", "5=2D1=", 0), ["0:TCA>T:DEL"]);
+        // Indels at start of target are anchored after them.
+        assert_eq!(variants("GGACG", "ACG", "2I3=", 0), ["0:A>GGA:INS"]);
+        assert_eq!(variants("CG", "TACG", "2D2=", 0), ["0:TAC>C:DEL"]);
+        // Anchor before start of alignment.
+        assert_eq!(variants("ATTC", "GGACC", "1=2I1=", 2), ["2:A>ATT:INS"]);
+
+        let path = "3D".parse::<Cigar>().unwrap().to_path().unwrap();
+        assert!(call_variants("", "ACG", &path, 0).is_err());
+        assert!(call_variants("", "ACG", &path, 1).is_err());
+    }
+
+    #[test]
+    fn test_call_variants_complex() {
+        // Indels next to mismatches are one variant without anchor.
+        assert_eq!(variants("AGTC", "ACC", "1=1X1I1=", 0), ["1:C>GT:COMPLEX"]);
+        assert_eq!(variants("GTAC", "CAC", "1I1X2=", 0), ["0:C>GT:COMPLEX"]);
+        assert_eq!(variants("AGC", "ACTC", "1=1X1D1=", 0), ["1:CT>G:COMPLEX"]);
+        // Back-to-back insertion and deletion.
+        assert_eq!(variants("AGC", "ATC", "1=1I1D1=", 0), ["1:T>G:SNV"]);
+        assert_eq!(variants("AGGC", "ATC", "1=2I1D1=", 0), ["1:T>GG:COMPLEX"]);
+        assert_eq!(variants("AGC", "AGC", "1=1I1D1=", 0), Vec::<String>::new());
+        // Indels at start of target sharing anchor are merged.
+        assert_eq!(variants("CA", "TC", "1D1=1I", 0), ["0:TC>CA:MNV"]);
+        assert_eq!(
+            variants("ACT", "TACG", "1D2=1I", 0),
+            ["0:TA>A:DEL", "2:C>CT:INS"]
+        );
+    }
+
+    #[test]
+    fn test_variant_kind() {
+        let variant = |reference: &str, alternate: &str| Variant {
+            pos: 0,
+            reference: reference.to_string(),
+            alternate: alternate.to_string(),
+        };
+        assert_eq!(variant("A", "AT").kind(), VariantKind::Insertion);
+        assert_eq!(variant("A", "TA").kind(), VariantKind::Insertion);
+        assert_eq!(variant("CAT", "T").kind(), VariantKind::Deletion);
+        assert_eq!(variant("C", "GT").kind(), VariantKind::Complex);
+        assert_eq!(variant("AC", "GT").kind(), VariantKind::Mnv);
+    }
+
+    #[test]
+    fn test_write_vcf() {
+        let variants = [
+            Variant {
+                pos: 0,
+                reference: "TCA".to_string(),
+                alternate: "T".to_string(),
+            },
+            Variant {
+                pos: 6,
+                reference: "A".to_string(),
+                alternate: "G".to_string(),
+            },
+        ];
+        let mut vcf = vec![];
+        write_vcf(&mut vcf, "chr1", 8, &variants).unwrap();
+        let vcf = String::from_utf8(vcf).unwrap();
+        let lines: Vec<&str> = vcf.lines().collect();
+        assert_eq!(lines[0], "##fileformat=VCFv4.2");
+        assert_eq!(lines[2], "##contig=<ID=chr1,length=8>");
+        assert_eq!(lines[4], "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO");
+        assert_eq!(lines[5], "chr1\t1\t.\tTCA\tT\t.\tPASS\tTYPE=DEL");
+        assert_eq!(lines[6], "chr1\t7\t.\tA\tG\t.\tPASS\tTYPE=SNV");
+    }
+}
@@ -0,0 +1,364 @@
+//! Conformance of [`Alignment::run`] to the plain dynamic programming of [`Alignment::run_naive`].
+
+use rs_edlib::{
+    align::Alignment,
+    cigar::EditOp,
+    config::{AlignConfig, KGrowth},
+    equal::{EqualityDefinition, EqualityPair},
+    mode::AlignMode,
+    simulate::{Burst, ErrorProfile, Prng, Simulator},
+    task::AlignTask,
+};
+
+const DNA: &str = "ACGT";
+const BINARY: &str = "01";
+const PROTEIN: &str = "ACDEFGHIKLMNPQRSTVWY";
+const WIDE: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Lengths around block boundaries of 64 cells.
+const BOUNDARY_LENGTHS: [usize; 9] = [0, 1, 2, 63, 64, 65, 127, 128, 129];
+
+const MODES: [AlignMode; 3] = [AlignMode::NW, AlignMode::SHW, AlignMode::HW];
+
+/// Sequence of random symbols of alphabet.
+fn sequence(rng: &mut Prng, alphabet: &str, len: usize) -> String {
+    let symbols: Vec<char> = alphabet.chars().collect();
+    (0..len)
+        .map(|_| symbols[rng.below(symbols.len())])
+        .collect()
+}
+
+/// Length around block boundaries or below 200.
+fn length(rng: &mut Prng) -> usize {
+    if rng.below(2) == 0 {
+        BOUNDARY_LENGTHS[rng.below(BOUNDARY_LENGTHS.len())]
+    } else {
+        rng.below(200)
+    }
+}
+
+/// Copy of sequence with some substitutions, insertions and deletions.
+fn mutate(rng: &mut Prng, alphabet: &str, seq: &str, num_edits: usize) -> String {
+    let mut seq: Vec<char> = seq.chars().collect();
+    for _ in 0..num_edits {
+        let pos = rng.below(seq.len() + 1);
+        let symbol = sequence(rng, alphabet, 1).chars().next().unwrap();
+        match rng.below(3) {
+            0 if pos < seq.len() => seq[pos] = symbol,
+            1 if pos < seq.len() => {
+                seq.remove(pos);
+            }
+            _ => seq.insert(pos, symbol),
+        }
+    }
+    seq.into_iter().collect()
+}
+
+/// Check that path aligns query to target between locations with edit distance.
+fn check_path(config: &AlignConfig, query: &str, target: &str, aln: &Alignment, case: &str) {
+    let path = aln.alignment.as_ref().expect(case);
+    let start = aln.start_locations.as_ref().expect(case)[0];
+    let end = aln.end_locations.as_ref().expect(case)[0];
+    let target = &target[usize::try_from(start).unwrap()..usize::try_from(end + 1).unwrap()];
+
+    let mut alphabet: String = query.chars().chain(target.chars()).collect();
+    alphabet.extend(
+        config
+            .added_equalities
+            .iter()
+            .flat_map(|pair| [pair.first, pair.second]),
+    );
+    let equality_def = EqualityDefinition::new(&alphabet, Some(&config.added_equalities));
+
+    let (mut query_chars, mut target_chars) = (query.chars(), target.chars());
+    let mut cost = 0;
+    for op in path {
+        match op {
+            EditOp::Match | EditOp::Mismatch => {
+                let (q, t) = (query_chars.next(), target_chars.next());
+                let (Some(q), Some(t)) = (q, t) else {
+                    panic!("{case}: Path is longer than sequences.")
+                };
+                let is_equal = equality_def.are_equal(q, t).unwrap();
+                assert_eq!(
+                    is_equal,
+                    *op == EditOp::Match,
+                    "{case}: {op:?} of {q} and {t}."
+                );
+                cost += usize::from(!is_equal);
+            }
+            EditOp::Insert => {
+                assert!(
+                    query_chars.next().is_some(),
+                    "{case}: Path is longer than query."
+                );
+                cost += 1;
+            }
+            EditOp::Delete => {
+                assert!(
+                    target_chars.next().is_some(),
+                    "{case}: Path is longer than target."
+                );
+                cost += 1;
+            }
+        }
+    }
+    assert!(query_chars.next().is_none(), "{case}: Query not consumed.");
+    assert!(
+        target_chars.next().is_none(),
+        "{case}: Target not consumed."
+    );
+    assert_eq!(Some(cost), aln.edit_distance, "{case}: Cost of path.");
+}
+
+/// Compare alignment to oracle.
+fn check(config: &AlignConfig, query: &str, target: &str, case: &str) {
+    let aln = Alignment::run(config.clone(), query, target).expect(case);
+    let expected = Alignment::run_naive(config.clone(), query, target).expect(case);
+    assert_eq!(
+        aln.edit_distance, expected.edit_distance,
+        "{case}: Edit distance."
+    );
+    assert_eq!(
+        aln.end_locations, expected.end_locations,
+        "{case}: End locations."
+    );
+    assert_eq!(
+        aln.start_locations, expected.start_locations,
+        "{case}: Start locations."
+    );
+    assert_eq!(
+        aln.exhausted_k, expected.exhausted_k,
+        "{case}: Exhausted k."
+    );
+    if config.task == AlignTask::Path && aln.edit_distance.is_some() {
+        check_path(config, query, target, &aln, case);
+    }
+}
+
+fn random_config(rng: &mut Prng, query_len: usize) -> AlignConfig {
+    AlignConfig {
+        mode: MODES[rng.below(MODES.len())].clone(),
+        task: [AlignTask::Distance, AlignTask::Loc, AlignTask::Path][rng.below(3)].clone(),
+        k: (rng.below(3) == 0).then(|| rng.below(query_len + 10)),
+        k_fraction: (rng.below(4) == 0).then(|| rng.below(50) as f64 / 100.0),
+        k_growth: random_k_growth(rng, query_len),
+        ..Default::default()
+    }
+}
+
+/// Default growth, or growth from small start, sometimes capped.
+fn random_k_growth(rng: &mut Prng, query_len: usize) -> KGrowth {
+    if rng.below(2) == 0 {
+        return KGrowth::default();
+    }
+    KGrowth {
+        start: 1 + rng.below(8),
+        factor: 2 + rng.below(2),
+        max: (rng.below(2) == 0).then(|| rng.below(query_len + 10)),
+    }
+}
+
+#[test]
+fn test_conformance_random() {
+    let mut rng = Prng::new(0x9E37_79B9_7F4A_7C15);
+    for case_idx in 0..1500 {
+        let alphabet = [DNA, BINARY, PROTEIN, WIDE][case_idx % 4];
+        let query_len = length(&mut rng);
+        let query = sequence(&mut rng, alphabet, query_len);
+        let target = match rng.below(3) {
+            // Unrelated.
+            0 => {
+                let target_len = length(&mut rng);
+                sequence(&mut rng, alphabet, target_len)
+            }
+            // Similar.
+            1 => {
+                let num_edits = rng.below(query_len / 4 + 2);
+                mutate(&mut rng, alphabet, &query, num_edits)
+            }
+            // Similar and surrounded.
+            _ => {
+                let num_edits = rng.below(query_len / 4 + 2);
+                let (prefix_len, suffix_len) = (rng.below(70), rng.below(70));
+                format!(
+                    "{}{}{}",
+                    sequence(&mut rng, alphabet, prefix_len),
+                    mutate(&mut rng, alphabet, &query, num_edits),
+                    sequence(&mut rng, alphabet, suffix_len)
+                )
+            }
+        };
+        let config = random_config(&mut rng, query_len);
+        let case = format!("Case {case_idx} {config:?} of {query} and {target}");
+        check(&config, &query, &target, &case);
+    }
+}
+
+#[test]
+fn test_conformance_added_equalities() {
+    let mut rng = Prng::new(0x2545_F491_4F6C_DD1D);
+    // Wildcard in target, like unknown base N.
+    let added_equalities: Vec<EqualityPair> = DNA
+        .chars()
+        .map(|first| EqualityPair { first, second: 'N' })
+        .chain([EqualityPair {
+            first: 'A',
+            second: 'G',
+        }])
+        .collect();
+    for case_idx in 0..300 {
+        let query_len = length(&mut rng);
+        let query = sequence(&mut rng, DNA, query_len);
+        let num_edits = rng.below(query_len / 4 + 2);
+        let target = mutate(&mut rng, "ACGTNN", &query, num_edits);
+        let config = AlignConfig {
+            added_equalities: added_equalities.clone(),
+            ..random_config(&mut rng, query_len)
+        };
+        let case = format!("Case {case_idx} {config:?} of {query} and {target}");
+        check(&config, &query, &target, &case);
+    }
+}
+
+#[test]
+fn test_conformance_long() {
+    let mut rng = Prng::new(0xD1B5_4A32_D192_ED03);
+    for case_idx in 0..4 {
+        let query_len = 300 + rng.below(600);
+        let query = sequence(&mut rng, DNA, query_len);
+        let target = format!(
+            "{}{}",
+            sequence(&mut rng, DNA, 100),
+            mutate(&mut rng, DNA, &query, query_len / 10)
+        );
+        for mode in MODES {
+            let config = AlignConfig {
+                mode,
+                task: AlignTask::Path,
+                ..Default::default()
+            };
+            let case = format!("Long case {case_idx} {config:?}");
+            check(&config, &query, &target, &case);
+        }
+    }
+}
+
+#[test]
+fn test_conformance_simulated() {
+    let profile = ErrorProfile {
+        homopolymer_bias: 3.0,
+        burst: Some(Burst {
+            rate: 0.005,
+            mean_len: 8,
+            error_rate: 0.4,
+        }),
+        ..ErrorProfile::uniform(0.08)
+    };
+    let mut simulator = Simulator::new(DNA, profile, 11).unwrap();
+    for case_idx in 0..60 {
+        let source_len = 50 + simulator.rng.below(150);
+        let sim = simulator.embedded_read(source_len, 40).unwrap();
+        let mode = MODES[case_idx % MODES.len()].clone();
+        let config = AlignConfig {
+            mode: mode.clone(),
+            task: AlignTask::Path,
+            ..Default::default()
+        };
+        let case = format!("Simulated case {case_idx} {config:?}");
+        check(&config, &sim.read, &sim.target, &case);
+        if mode == AlignMode::HW {
+            let aln = Alignment::run(config, &sim.read, &sim.target).unwrap();
+            assert!(aln.edit_distance.unwrap() <= sim.num_errors(), "{case}");
+        }
+    }
+}
+
+/// Hand-written cases with known edit distances, checked in every mode and task.
+#[test]
+fn test_conformance_known_cases() {
+    // Query, target, and edit distance for NW, SHW and HW.
+    let cases = [
+        ("ACGT", "ACGT", [0, 0, 0]),
+        ("match", "remachine", [6, 3, 1]),
+        ("remachine", "match", [6, 6, 6]),
+        ("ACGTTGCA", "ACGTTGCA", [0, 0, 0]),
+        ("AACTGGTTACGTACG", "AACTGGCCTTACGTACG", [2, 2, 2]),
+        ("ACGT", "AGGCT", [2, 2, 2]),
+        ("AAAA", "CCCCCCCCCC", [10, 4, 4]),
+        ("A", "CCCCA", [4, 1, 0]),
+        ("CCCA", "A", [3, 3, 3]),
+        ("TTTT", "AAGGTTTTCC", [6, 4, 0]),
+    ];
+    for (query, target, edit_distances) in cases {
+        for (mode, edit_distance) in MODES.into_iter().zip(edit_distances) {
+            for task in [AlignTask::Distance, AlignTask::Loc, AlignTask::Path] {
+                let config = AlignConfig {
+                    mode: mode.clone(),
+                    task,
+                    ..Default::default()
+                };
+                let case = format!("{config:?} of {query} and {target}");
+                let aln = Alignment::run(config.clone(), query, target).unwrap();
+                assert_eq!(aln.edit_distance, Some(edit_distance), "{case}");
+                check(&config, query, target, &case);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_conformance_k_growth_max() {
+    let mut rng = Prng::new(0x94D0_49BB_1331_11EB);
+    for case_idx in 0..300 {
+        let query_len = length(&mut rng);
+        let query = sequence(&mut rng, DNA, query_len);
+        let num_edits = rng.below(query_len / 2 + 4);
+        let target = mutate(&mut rng, DNA, &query, num_edits);
+        // Cap around number of edits, so alignments are found on either side of it.
+        let config = AlignConfig {
+            k_growth: KGrowth {
+                start: 1,
+                factor: 2,
+                max: Some(rng.below(num_edits + 2)),
+            },
+            ..random_config(&mut rng, query_len)
+        };
+        let config = AlignConfig {
+            k: None,
+            k_fraction: None,
+            ..config
+        };
+        let case = format!("Case {case_idx} {config:?} of {query} and {target}");
+        check(&config, &query, &target, &case);
+    }
+}
+
+#[test]
+fn test_conformance_empty() {
+    for mode in MODES {
+        for (query, target) in [("", ""), ("", "ACGT"), ("ACGT", "")] {
+            let config = AlignConfig {
+                mode: mode.clone(),
+                task: AlignTask::Path,
+                ..Default::default()
+            };
+            check(
+                &config,
+                query,
+                target,
+                &format!("{config:?} of {query} and {target}"),
+            );
+            let config = AlignConfig {
+                k: Some(1),
+                ..config
+            };
+            check(
+                &config,
+                query,
+                target,
+                &format!("{config:?} of {query} and {target}"),
+            );
+        }
+    }
+}
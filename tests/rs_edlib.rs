@@ -5,8 +5,6 @@ fn test_usage() {
     let query: &str = "hello";
     let target: &str = "world!";
     let align_res = Alignment::run(AlignConfig::default(), query, target).unwrap();
-    println!(
-        "edit_distance('{query}', '{target}') = {:?}",
-        align_res.edit_distance
-    )
+    assert_eq!(align_res.edit_distance, Some(5));
+    assert_eq!(align_res.end_locations, Some(vec![5]));
 }